@@ -0,0 +1,180 @@
+//! Arbitrary output variables: diagnostic layers about what camera rays hit first, rendered
+//! instead of or alongside the path traced image.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use image::{ImageBuffer, ImageFormat, Rgb, RgbImage};
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    camera::Camera,
+    framebuffer::Framebuffer,
+    heatmap::gradient,
+    hittable::{
+        bvh::{count_traversals, TraversalCounts},
+        Hittable,
+    },
+    output::{save, OutputFormat},
+    ray::Ray,
+    rng::mix,
+    tonemap::{linear_to_srgb, ToneMapping},
+    vec::Color,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Aov {
+    /// The path traced image.
+    #[default]
+    #[value(alias = "path")]
+    Beauty,
+    /// Shading normal at the first hit.
+    Normal,
+    /// World position of the first hit.
+    Position,
+    /// Distance of the first hit along the camera's view direction.
+    Depth,
+    /// Reflectance of the material at the first hit.
+    Albedo,
+    /// Number of the material at the first hit, from one in scene order.
+    MaterialId,
+    /// Texture coordinates at the first hit.
+    Uv,
+    /// Bounding box tests in red and primitive intersections in green of the camera ray.
+    BvhCost,
+}
+
+impl Aov {
+    /// Whether a pixel shows the average of its samples. Ids, depths and positions keep the first
+    /// sample instead, an average of them names a surface that isn't there.
+    pub fn is_averaged(&self) -> bool {
+        !matches!(self, Aov::MaterialId | Aov::Depth | Aov::Position)
+    }
+}
+
+// Numbers the materials of a scene in the order objects were added, from one.
+pub(crate) struct MaterialIds(HashMap<usize, u32>);
+
+impl MaterialIds {
+    pub fn new(models: &[Box<dyn Hittable>]) -> Self {
+        let mut ids = HashMap::new();
+        for model in models {
+            model.for_each_material(&mut |material| {
+                let next = ids.len() as u32 + 1;
+                ids.entry(material.identity()).or_insert(next);
+            });
+        }
+        Self(ids)
+    }
+}
+
+/// Values of `aovs` for a camera ray, zero where it hits nothing. `Beauty` isn't a first hit
+/// layer and is left at zero.
+pub(crate) fn first_hit<H: Hittable>(
+    ray: &Ray,
+    world: &H,
+    camera: &Camera,
+    material_ids: &MaterialIds,
+    aovs: &[Aov],
+    values: &mut [Color],
+) {
+    // Counting slows every traversal down, so only rays that report it count.
+    let (hit, traversal) = if aovs.contains(&Aov::BvhCost) {
+        count_traversals(|| world.hit(ray, 0.001, f32::INFINITY))
+    } else {
+        let hit = world.hit(ray, 0.001, f32::INFINITY);
+        (hit, TraversalCounts::default())
+    };
+
+    for (aov, value) in aovs.iter().zip(values) {
+        *value = match (aov, &hit) {
+            (Aov::BvhCost, _) => Color::new(
+                traversal.aabb_tests as f32,
+                traversal.primitive_tests as f32,
+                0.0,
+            ),
+            (Aov::Beauty, _) | (_, None) => Color::zeros(),
+            (Aov::Normal, Some(hit)) => hit.normal.normalize(),
+            (Aov::Position, Some(hit)) => hit.point,
+            (Aov::Depth, Some(hit)) => Color::repeat(camera.depth(&hit.point)),
+            (Aov::Albedo, Some(hit)) => hit.material.albedo(hit),
+            (Aov::MaterialId, Some(hit)) => Color::repeat(
+                material_ids
+                    .0
+                    .get(&hit.material.identity())
+                    .copied()
+                    .unwrap_or(0) as f32,
+            ),
+            (Aov::Uv, Some(hit)) => Color::new(hit.u, hit.v, 0.0),
+        };
+    }
+}
+
+/// Writes a layer in the format given by the extension of `path`.
+///
+/// `.hdr`, `.exr` and `.pfm` keep the values, `.png` and `.jpg` show them as colours: normals
+/// mapped from `[-1, 1]`, positions and depths scaled to the range of the image, a colour per
+/// material and a heatmap of the BVH cost. The beauty layer is tone mapped with `tone_mapping`.
+pub fn save_layer(
+    aov: Aov,
+    framebuffer: &Framebuffer,
+    path: &Path,
+    tone_mapping: &ToneMapping,
+) -> Result<()> {
+    let format = match OutputFormat::from_path(path)? {
+        OutputFormat::Png => ImageFormat::Png,
+        OutputFormat::Jpeg => ImageFormat::Jpeg,
+        _ => return save(framebuffer, path, tone_mapping),
+    };
+    if aov == Aov::Beauty {
+        return save(framebuffer, path, tone_mapping);
+    }
+    visualize(aov, framebuffer)
+        .save_with_format(path, format)
+        .map_err(Into::into)
+}
+
+/// The layer as 8-bit colours, see [`save_layer`].
+pub fn visualize(aov: Aov, framebuffer: &Framebuffer) -> RgbImage {
+    let pixels = framebuffer.pixels();
+    let max = pixels
+        .iter()
+        .fold(Color::zeros(), |max, c| max.sup(c))
+        .map(|c| c.max(f32::MIN_POSITIVE));
+    let min = pixels
+        .iter()
+        .fold(Color::repeat(f32::INFINITY), |min, c| min.inf(c));
+
+    let display = |c: Color| -> Color {
+        match aov {
+            Aov::Beauty | Aov::Albedo => c.map(|c| linear_to_srgb(c.clamp(0.0, 1.0))),
+            Aov::Normal => c.map(|c| c * 0.5 + 0.5),
+            Aov::Position => (c - min).component_div(&(max - min).map(|c| c.max(f32::EPSILON))),
+            // Near is bright, misses are black.
+            Aov::Depth if c.x <= 0.0 => Color::zeros(),
+            Aov::Depth => Color::repeat(1.0 - c.x / max.x),
+            Aov::MaterialId => material_color(c.x.round() as u32),
+            Aov::Uv => c,
+            Aov::BvhCost => gradient((c.x + c.y) / (max.x + max.y)),
+        }
+    };
+
+    ImageBuffer::from_fn(framebuffer.width(), framebuffer.height(), |x, y| {
+        let c = display(framebuffer.get(x, y)).map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Rgb([c.x, c.y, c.z])
+    })
+}
+
+// A colour that sets a material apart from its neighbours, black for no material.
+fn material_color(id: u32) -> Color {
+    if id == 0 {
+        return Color::zeros();
+    }
+    let hash = mix(id as u64);
+    Color::new(
+        (hash & 0xff) as f32,
+        (hash >> 8 & 0xff) as f32,
+        (hash >> 16 & 0xff) as f32,
+    ) / 255.0
+        * 0.8
+        + Color::repeat(0.2)
+}
@@ -0,0 +1,32 @@
+use crate::{ray::Ray, vec::Color};
+
+#[derive(Copy, Clone, Debug)]
+pub enum Background {
+    None,
+    Solid(Color),
+    Gradient { bottom: Color, top: Color },
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background {
+    #[inline(always)]
+    pub fn color(&self, ray: &Ray) -> Color {
+        match *self {
+            Background::None => Color::zeros(),
+            Background::Solid(color) => color,
+            Background::Gradient { bottom, top } => {
+                let unit_dir = ray.direction().normalize();
+                let t = 0.5 * (unit_dir.y + 1.0);
+                (1.0 - t) * bottom + t * top
+            }
+        }
+    }
+}
@@ -1,14 +1,89 @@
-use anyhow::{Ok, Result};
+use anyhow::{anyhow, Ok, Result};
 use clap::Parser;
-use ray_tracing_one_weekend::{cli::Cli, draw};
-use std::path::Path;
+use ray_tracing_one_weekend::{
+    aov::save_layer,
+    cli::Cli,
+    heatmap::heatmap,
+    output::OutputFormat,
+    scene::{file, random_scene},
+    CancellationToken, Renderer,
+};
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    // Fail on an unknown extension before spending time on the render.
+    OutputFormat::from_path(&cli.output)?;
+    if let Some(ref preview) = cli.preview {
+        OutputFormat::from_path(preview)?;
+    }
+    for (_, path) in &cli.aovs {
+        OutputFormat::from_path(path)?;
+    }
+    if let Some(ref heatmap) = cli.heatmap {
+        // An 8-bit image, it has no linear values for the float formats.
+        if !matches!(
+            OutputFormat::from_path(heatmap)?,
+            OutputFormat::Png | OutputFormat::Jpeg
+        ) {
+            return Err(anyhow!(
+                "the heatmap can only be written as png or jpg, not `{}`",
+                heatmap.display()
+            ));
+        }
+    }
 
-    let path = Path::new("image.png");
-    let image = draw(cli.height, cli.width, cli.samples, cli.depth)?;
+    let settings = cli.render_settings();
+    if let Some(crop) = settings.crop {
+        crop.resolve(settings.width, settings.height)?;
+    }
+    let scene = match cli.scene {
+        Some(ref scene) => file::load(scene, settings.aspect_ratio(), settings.seed)?,
+        None => random_scene(settings.aspect_ratio(), settings.seed),
+    };
 
-    image.save_with_format(path, image::ImageFormat::Png)?;
+    // The first Ctrl-C stops the render and saves what it has, the second one quits.
+    let cancellation = CancellationToken::new();
+    let handler_cancellation = cancellation.clone();
+    ctrlc::set_handler(move || {
+        if handler_cancellation.is_cancelled() {
+            std::process::exit(130);
+        }
+        eprintln!("Stopping, press Ctrl-C again to quit without saving");
+        handler_cancellation.cancel();
+    })?;
+
+    let mut renderer = Renderer::new()
+        .with_progress(cli.progress_sink())
+        .with_cancellation(cancellation);
+    if let Some(preview) = cli.preview.clone() {
+        let tone_mapping = cli.tone_mapping();
+        let integrator = cli.integrator;
+        renderer = renderer.with_preview(cli.preview_interval, move |framebuffer| {
+            if let Err(err) = save_layer(integrator, framebuffer, &preview, &tone_mapping) {
+                eprintln!("Failed to write preview {}: {}", preview.display(), err);
+            }
+        });
+    }
+    let output = renderer.render_output(&scene, &settings)?;
+
+    let tone_mapping = cli.tone_mapping();
+    save_layer(
+        cli.integrator,
+        &output.framebuffer,
+        &cli.output,
+        &tone_mapping,
+    )?;
+    for ((aov, framebuffer), (_, path)) in output.aovs.iter().zip(&cli.aovs) {
+        save_layer(*aov, framebuffer, path, &tone_mapping)?;
+    }
+    let sample_counts = output.sample_counts;
+    if let Some(ref path) = cli.heatmap {
+        eprintln!(
+            "📊 {:.1} samples per pixel on average, {} at most",
+            sample_counts.mean(),
+            sample_counts.max()
+        );
+        heatmap(&sample_counts).save(path)?;
+    }
     Ok(())
 }
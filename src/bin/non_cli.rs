@@ -2,6 +2,6 @@ use anyhow::{Ok, Result};
 use ray_tracing_one_weekend::draw;
 
 fn main() -> Result<()> {
-    draw(128, 256, 512, 50)?;
+    draw(128, 256, 512, 50, 0)?;
     Ok(())
 }
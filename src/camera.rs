@@ -1,17 +1,10 @@
-use rand::{rngs::SmallRng, Rng};
-
-use crate::{ray::Ray, vec::Vec3};
-
-fn random_in_unit_disk(rng: &mut SmallRng) -> Vec3 {
-    let unit = Vec3::new(1.0, 1.0, 0.0);
-    loop {
-        let p = 2.0 * Vec3::new(rng.gen::<f32>(), rng.gen::<f32>(), 0.0) - unit;
-        if p.dot(&p) < 1.0 {
-            return p;
-        }
-    }
-}
+use crate::{
+    ray::Ray,
+    sampler::warp::concentric_disk,
+    vec::{Vec2, Vec3},
+};
 
+/// A thin lens camera.
 pub struct Camera {
     origin: Vec3,
     lower_left_corner: Vec3,
@@ -20,9 +13,14 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f32,
+    shutter_open: f32,
+    shutter_close: f32,
 }
 
 impl Camera {
+    /// `vertical_fov` is in degrees and `aspect` should be the width over the height of the
+    /// rendered image. A zero `aperture` gives a pinhole camera, otherwise objects at `focus_dist`
+    /// from `look_from` are in focus. The shutter is open from time 0 to 1.
     pub fn new(
         look_from: Vec3,
         look_at: Vec3,
@@ -47,16 +45,63 @@ impl Camera {
             u,
             v,
             lens_radius: aperture / 2.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
         }
     }
 
+    /// Rays are spread over the times from `open` to `close`, blurring objects that move then.
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        self
+    }
+
+    /// Distance of `point` in front of the camera, along its view direction.
+    #[inline(always)]
+    pub fn depth(&self, point: &Vec3) -> f32 {
+        (point - self.origin).dot(&self.v.cross(&self.u))
+    }
+
+    /// Ray through the point `(u, v)` of the image, from `(0, 0)` at the bottom left to `(1, 1)`.
+    /// `lens` is a uniform sample in `[0, 1)²` that picks the point on the lens and `time` one in
+    /// `[0, 1)` that picks the time within the shutter interval.
     #[inline(always)]
-    pub fn get_ray(&self, u: f32, v: f32, rng: &mut SmallRng) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk(rng);
+    pub fn get_ray(&self, u: f32, v: f32, lens: Vec2, time: f32) -> Ray {
+        let rd = self.lens_radius * concentric_disk(lens);
         let offset = self.u * rd.x + self.v * rd.y;
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + u * self.horizontal + v * self.vertical - self.origin - offset,
         )
+        .with_time(self.shutter_open + time * (self.shutter_close - self.shutter_open))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_are_spread_over_the_shutter_interval() {
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 5.0),
+            Vec3::zeros(),
+            Vec3::y(),
+            40.0,
+            2.0,
+            0.1,
+            5.0,
+        )
+        .with_shutter(0.25, 0.75);
+        let lens = Vec2::new(0.3, 0.6);
+        assert_eq!(camera.get_ray(0.5, 0.5, lens, 0.0).time(), 0.25);
+        assert_eq!(camera.get_ray(0.5, 0.5, lens, 0.5).time(), 0.5);
+        for i in 0..100 {
+            let time = camera.get_ray(0.1, 0.9, lens, i as f32 / 100.0).time();
+            assert!((0.25..=0.75).contains(&time), "{}", time);
+        }
+        let time = camera.get_ray(0.5, 0.5, lens, 1.0 - f32::EPSILON).time();
+        assert!(time <= 0.75, "{}", time);
     }
 }
@@ -1,4 +1,23 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use crate::{
+    aov::Aov,
+    crop::CropWindow,
+    filter::{Filter, FilterKind},
+    progress::{IndicatifProgress, LogProgress, ProgressSink, SilentProgress},
+    sampler::SamplerKind,
+    tiles::TileOrder,
+    tonemap::{ToneMapping, ToneOperator},
+    AdaptiveSampling, RenderSettings,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ProgressOutput {
+    Bar,
+    Log,
+    None,
+}
 
 #[derive(Parser)]
 #[clap(
@@ -6,12 +25,320 @@ use clap::Parser;
     about = "Rust implementation of `Ray tracing in one weekend`"
 )]
 pub struct Cli {
+    #[clap(help = "scene description file (TOML), renders the book cover when omitted")]
+    pub scene: Option<PathBuf>,
     #[clap(default_value = "256", help = "image width", short)]
     pub width: u32,
-    #[clap(default_value = "128", help = "image height", short)]
+    #[clap(default_value = "128", help = "image height", short = 'H')]
     pub height: u32,
-    #[clap(default_value = "500", help = "samples par pixel", short)]
+    #[clap(
+        default_value = "500",
+        help = "samples par pixel, the maximum with --adaptive, 0 to render until --time or --noise-threshold",
+        short
+    )]
     pub samples: u32,
     #[clap(default_value = "50", help = "max depth", short)]
     pub depth: u32,
+    #[clap(
+        default_value = "5",
+        help = "bounces after which russian roulette may end a path",
+        long
+    )]
+    pub roulette_depth: u32,
+    #[clap(
+        default_value = "image.png",
+        help = "output image, the format follows the extension (png, jpg, hdr, exr, pfm)",
+        short,
+        long
+    )]
+    pub output: PathBuf,
+    #[clap(help = "seed for reproducible renders, random when omitted", long)]
+    pub seed: Option<u64>,
+    #[clap(
+        default_value = "clamp",
+        help = "tone mapping operator for 8-bit output",
+        long,
+        value_enum
+    )]
+    pub tonemap: ToneOperator,
+    #[clap(
+        default_value = "0",
+        help = "exposure in stops, applied before tone mapping",
+        long,
+        allow_negative_numbers = true
+    )]
+    pub exposure: f32,
+    #[clap(
+        default_value = "4",
+        help = "luminance mapped to white by extended-reinhard",
+        long
+    )]
+    pub white: f32,
+    #[clap(
+        default_value = "bar",
+        help = "how to report progress",
+        long,
+        value_enum
+    )]
+    pub progress: ProgressOutput,
+    #[clap(default_value = "32", help = "edge length of render tiles", long)]
+    pub tile_size: u32,
+    #[clap(
+        default_value = "spiral",
+        help = "order tiles are rendered in",
+        long,
+        value_enum
+    )]
+    pub tile_order: TileOrder,
+    #[clap(
+        help = "render only the pixels X,Y,WIDTH,HEIGHT, from the top left",
+        long,
+        value_name = "X,Y,WIDTH,HEIGHT",
+        value_parser = parse_crop_pixels,
+        conflicts_with = "crop_window"
+    )]
+    pub crop: Option<CropWindow>,
+    #[clap(
+        help = "render only the window between two corners given as fractions of the image size",
+        long,
+        value_name = "X0,Y0,X1,Y1",
+        value_parser = parse_crop_window
+    )]
+    pub crop_window: Option<CropWindow>,
+    #[clap(
+        help = "keep the full image size when cropping, black outside the crop",
+        long
+    )]
+    pub crop_full_frame: bool,
+    #[clap(
+        help = "stop after this long, e.g. 90s, 5m or 1h30m",
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration
+    )]
+    pub time: Option<Duration>,
+    #[clap(
+        help = "stop once the mean relative error of the pixels is below this, e.g. 0.01",
+        long
+    )]
+    pub noise_threshold: Option<f32>,
+    #[clap(
+        default_value = "4",
+        help = "samples added to each pixel per pass",
+        long
+    )]
+    pub samples_per_pass: u32,
+    #[clap(help = "write the image so far to this file while rendering", long)]
+    pub preview: Option<PathBuf>,
+    #[clap(
+        default_value = "10s",
+        help = "minimum time between preview writes",
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration
+    )]
+    pub preview_interval: Duration,
+    #[clap(
+        help = "stop sampling a pixel once its 95% confidence interval is within this fraction of its brightness, e.g. 0.05",
+        long,
+        value_name = "THRESHOLD"
+    )]
+    pub adaptive: Option<f32>,
+    #[clap(
+        default_value = "16",
+        help = "samples every pixel takes before --adaptive may stop it",
+        long
+    )]
+    pub min_samples: u32,
+    #[clap(
+        help = "write an image of the samples taken per pixel (png or jpg)",
+        long
+    )]
+    pub heatmap: Option<PathBuf>,
+    #[clap(
+        default_value = "sobol",
+        help = "where sample points come from",
+        long,
+        value_enum
+    )]
+    pub sampler: SamplerKind,
+    #[clap(
+        default_value = "box",
+        help = "reconstruction filter that weights samples into the pixels around them",
+        long,
+        value_enum
+    )]
+    pub filter: FilterKind,
+    #[clap(
+        help = "filter radius in pixels, at least 0.5, defaults to 0.5 for box, 1 for tent, 1.5 for gaussian and 2 for mitchell and lanczos",
+        long
+    )]
+    pub filter_radius: Option<f32>,
+    #[clap(
+        default_value = "beauty",
+        help = "what the output image shows, the path traced image or a diagnostic view",
+        long,
+        value_enum
+    )]
+    pub integrator: Aov,
+    #[clap(
+        help = "also write a layer, e.g. normal=normal.exr, can be repeated",
+        long = "aov",
+        value_name = "LAYER=PATH",
+        value_parser = parse_aov
+    )]
+    pub aovs: Vec<(Aov, PathBuf)>,
+}
+
+fn parse_crop_pixels(s: &str) -> Result<CropWindow, String> {
+    let [x, y, width, height] = parse_four::<u32>(s)?;
+    Ok(CropWindow::Pixels {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+fn parse_crop_window(s: &str) -> Result<CropWindow, String> {
+    let [x_min, y_min, x_max, y_max] = parse_four::<f32>(s)?;
+    Ok(CropWindow::Normalized {
+        x_min,
+        y_min,
+        x_max,
+        y_max,
+    })
+}
+
+fn parse_four<T: FromStr>(s: &str) -> Result<[T; 4], String> {
+    let values = s
+        .split(',')
+        .map(|v| v.trim().parse::<T>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("`{}` is not four comma separated numbers", s))?;
+    values
+        .try_into()
+        .map_err(|_| format!("`{}` is not four comma separated numbers", s))
+}
+
+fn parse_aov(s: &str) -> Result<(Aov, PathBuf), String> {
+    let (name, path) = s.split_once('=').ok_or_else(|| {
+        format!(
+            "`{}` is not a layer and a path such as normal=normal.exr",
+            s
+        )
+    })?;
+    let aov = Aov::from_str(name.trim(), true)?;
+    Ok((aov, PathBuf::from(path)))
+}
+
+// A plain number of seconds, or numbers with `h`, `m`, `s` or `ms` units such as `1h30m`.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let error = || format!("`{}` is not a duration such as 90s, 5m or 1h30m", s);
+    if let Ok(seconds) = s.trim().parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).map_err(|_| error());
+    }
+
+    let mut seconds = 0.0;
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(error());
+    }
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(error)?;
+        let unit_end = rest[number_end..]
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .map_or(rest.len(), |i| number_end + i);
+        let number: f64 = rest[..number_end].parse().map_err(|_| error())?;
+        seconds += number
+            * match &rest[number_end..unit_end] {
+                "h" => 3600.0,
+                "m" => 60.0,
+                "s" => 1.0,
+                "ms" => 0.001,
+                _ => return Err(error()),
+            };
+        rest = &rest[unit_end..];
+    }
+    Duration::try_from_secs_f64(seconds).map_err(|_| error())
+}
+
+impl Cli {
+    // A random seed is drawn when none is given, so every call may return a different one.
+    pub fn render_settings(&self) -> RenderSettings {
+        RenderSettings {
+            width: self.width,
+            height: self.height,
+            samples_per_pixel: self.samples,
+            max_depth: self.depth,
+            roulette_depth: self.roulette_depth,
+            seed: self.seed.unwrap_or_else(rand::random),
+            tile_size: self.tile_size,
+            tile_order: self.tile_order,
+            crop: self.crop.or(self.crop_window),
+            crop_full_frame: self.crop_full_frame,
+            samples_per_pass: self.samples_per_pass,
+            time_limit: self.time,
+            noise_threshold: self.noise_threshold,
+            adaptive: self.adaptive.map(|threshold| AdaptiveSampling {
+                min_samples: self.min_samples,
+                threshold,
+            }),
+            sampler: self.sampler,
+            filter: self.filter(),
+            integrator: self.integrator,
+            aovs: self.aovs.iter().map(|(aov, _)| *aov).collect(),
+        }
+    }
+
+    pub fn filter(&self) -> Filter {
+        let filter = Filter::new(self.filter);
+        match self.filter_radius {
+            Some(radius) => filter.with_radius(radius),
+            None => filter,
+        }
+    }
+
+    pub fn progress_sink(&self) -> Box<dyn ProgressSink> {
+        match self.progress {
+            ProgressOutput::Bar => Box::new(IndicatifProgress::new()),
+            ProgressOutput::Log => Box::new(LogProgress::default()),
+            ProgressOutput::None => Box::new(SilentProgress),
+        }
+    }
+
+    pub fn tone_mapping(&self) -> ToneMapping {
+        ToneMapping {
+            operator: self.tonemap,
+            exposure: self.exposure,
+            white: self.white,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        let seconds = |s: &str| parse_duration(s).unwrap().as_secs_f64();
+        assert_eq!(seconds("90"), 90.0);
+        assert_eq!(seconds("1.5"), 1.5);
+        assert_eq!(seconds("90s"), 90.0);
+        assert_eq!(seconds("5m"), 300.0);
+        assert_eq!(seconds("1h30m"), 5400.0);
+        assert_eq!(seconds("2m30s"), 150.0);
+        assert_eq!(seconds("250ms"), 0.25);
+        assert_eq!(seconds(" 1.5h "), 5400.0);
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        for s in ["", "m", "5x", "5 m", "1h30", "-5", "-5s", "1e400", "h5"] {
+            assert!(parse_duration(s).is_err(), "{:?}", s);
+        }
+    }
 }
@@ -0,0 +1,160 @@
+use anyhow::{anyhow, Result};
+
+use crate::tiles::Tile;
+
+/// The part of the image to render, with `y` going down from the top like [`Tile`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CropWindow {
+    Pixels {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// Corners as fractions of the image size, `[0, 1]` on both axes.
+    Normalized {
+        x_min: f32,
+        y_min: f32,
+        x_max: f32,
+        y_max: f32,
+    },
+}
+
+impl CropWindow {
+    /// The pixels of a `width` by `height` image covered by the window.
+    pub fn resolve(&self, width: u32, height: u32) -> Result<Tile> {
+        let region = match *self {
+            CropWindow::Pixels {
+                x,
+                y,
+                width: crop_width,
+                height: crop_height,
+            } => {
+                if x.saturating_add(crop_width) > width || y.saturating_add(crop_height) > height {
+                    return Err(anyhow!(
+                        "crop window {}x{} at ({}, {}) is outside the {}x{} image",
+                        crop_width,
+                        crop_height,
+                        x,
+                        y,
+                        width,
+                        height
+                    ));
+                }
+                Tile {
+                    x,
+                    y,
+                    width: crop_width,
+                    height: crop_height,
+                }
+            }
+            CropWindow::Normalized {
+                x_min,
+                y_min,
+                x_max,
+                y_max,
+            } => {
+                let in_range = |min: f32, max: f32| 0.0 <= min && min < max && max <= 1.0;
+                if !in_range(x_min, x_max) || !in_range(y_min, y_max) {
+                    return Err(anyhow!(
+                        "crop window ({}, {}) to ({}, {}) must be ordered and within [0, 1]",
+                        x_min,
+                        y_min,
+                        x_max,
+                        y_max
+                    ));
+                }
+                // Rounding both corners up gives adjacent windows no gaps and no overlap.
+                let x = (x_min * width as f32).ceil() as u32;
+                let y = (y_min * height as f32).ceil() as u32;
+                Tile {
+                    x,
+                    y,
+                    width: ((x_max * width as f32).ceil() as u32).min(width) - x,
+                    height: ((y_max * height as f32).ceil() as u32).min(height) - y,
+                }
+            }
+        };
+
+        if region.pixels() == 0 {
+            return Err(anyhow!("crop window {:?} covers no pixels", self));
+        }
+        Ok(region)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(x_min: f32, y_min: f32, x_max: f32, y_max: f32) -> CropWindow {
+        CropWindow::Normalized {
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+        }
+    }
+
+    #[test]
+    fn pixels_within_the_image() {
+        let crop = CropWindow::Pixels {
+            x: 10,
+            y: 20,
+            width: 30,
+            height: 40,
+        };
+        let tile = crop.resolve(40, 60).unwrap();
+        assert_eq!((tile.x, tile.y, tile.width, tile.height), (10, 20, 30, 40));
+        assert!(crop.resolve(39, 60).is_err());
+        assert!(crop.resolve(40, 59).is_err());
+    }
+
+    #[test]
+    fn pixels_rejects_empty_and_overflowing_windows() {
+        let empty = CropWindow::Pixels {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 5,
+        };
+        assert!(empty.resolve(10, 10).is_err());
+        let overflowing = CropWindow::Pixels {
+            x: u32::MAX,
+            y: 0,
+            width: 2,
+            height: 2,
+        };
+        assert!(overflowing.resolve(10, 10).is_err());
+    }
+
+    #[test]
+    fn normalized_halves_tile_the_image() {
+        // Odd sizes, so the halves can't both round down or both round up.
+        let (width, height) = (101, 7);
+        let left = normalized(0.0, 0.0, 0.5, 1.0)
+            .resolve(width, height)
+            .unwrap();
+        let right = normalized(0.5, 0.0, 1.0, 1.0)
+            .resolve(width, height)
+            .unwrap();
+        assert_eq!(left.x + left.width, right.x);
+        assert_eq!(left.width + right.width, width);
+        assert_eq!((left.height, right.height), (height, height));
+    }
+
+    #[test]
+    fn normalized_rejects_bad_corners() {
+        for crop in [
+            normalized(0.5, 0.0, 0.5, 1.0),
+            normalized(0.6, 0.0, 0.4, 1.0),
+            normalized(-0.1, 0.0, 0.5, 1.0),
+            normalized(0.0, 0.0, 1.1, 1.0),
+            normalized(0.0, f32::NAN, 1.0, 1.0),
+            // Narrower than a pixel and between two pixel edges.
+            normalized(0.11, 0.0, 0.12, 1.0),
+        ] {
+            assert!(crop.resolve(4, 4).is_err(), "{:?}", crop);
+        }
+    }
+}
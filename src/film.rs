@@ -0,0 +1,388 @@
+use crate::{
+    aov::Aov,
+    filter::Filter,
+    framebuffer::Framebuffer,
+    heatmap::SampleCounts,
+    tiles::Tile,
+    vec::{luminance, Color},
+};
+
+// Added to the luminance when estimating relative noise, so near-black pixels don't dominate.
+const DARK_LUMINANCE: f32 = 0.01;
+
+// Running statistics of the samples of one pixel.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PixelStats {
+    samples: u32,
+    // Welford's running mean and sum of squared deviations of the sample luminance.
+    mean: f32,
+    m2: f32,
+}
+
+impl PixelStats {
+    #[inline(always)]
+    pub fn add(&mut self, color: Color) {
+        self.samples += 1;
+
+        let l = luminance(&color);
+        let delta = l - self.mean;
+        self.mean += delta / self.samples as f32;
+        self.m2 += delta * (l - self.mean);
+    }
+
+    // Chan et al.'s parallel variant of Welford's update.
+    pub fn merge(&mut self, other: &PixelStats) {
+        if other.samples == 0 {
+            return;
+        }
+        let samples = self.samples + other.samples;
+        let delta = other.mean - self.mean;
+
+        self.m2 += other.m2
+            + delta * delta * (self.samples as f32 * other.samples as f32 / samples as f32);
+        self.mean += delta * other.samples as f32 / samples as f32;
+        self.samples = samples;
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Standard error of the mean luminance relative to the luminance, `None` below two samples.
+    pub fn relative_error(&self) -> Option<f32> {
+        if self.samples < 2 {
+            return None;
+        }
+        let n = self.samples as f32;
+        let variance = self.m2 / (n - 1.0);
+        Some((variance / n).sqrt() / (self.mean.max(0.0) + DARK_LUMINANCE))
+    }
+}
+
+// Filter weighted sum of the samples around a pixel.
+#[derive(Copy, Clone, Debug, Default)]
+struct Splat {
+    color: Color,
+    weight: f32,
+}
+
+// The samples of a tile spread over the pixels they reach.
+pub struct SplatTile {
+    bounds: Tile,
+    splats: Vec<Splat>,
+}
+
+impl SplatTile {
+    // `bounds` must hold every pixel within the filter radius of the tile's samples.
+    pub fn new(bounds: Tile) -> Self {
+        Self {
+            bounds,
+            splats: vec![Splat::default(); bounds.pixels() as usize],
+        }
+    }
+
+    /// Sets pixel `(x, y)` to `color` regardless of the filter, for layers that keep one sample.
+    pub fn set(&mut self, x: u32, y: u32, color: Color) {
+        let index = (y - self.bounds.y) * self.bounds.width + (x - self.bounds.x);
+        self.splats[index as usize] = Splat { color, weight: 1.0 };
+    }
+
+    /// Adds a sample at `(x, y)` in pixels from the top left of the image.
+    #[inline(always)]
+    pub fn add(&mut self, filter: &Filter, x: f32, y: f32, color: Color) {
+        // Pixel centres within the radius, clipped to the bounds.
+        let x0 = ((x - 0.5 - filter.radius).ceil().max(self.bounds.x as f32)) as u32;
+        let y0 = ((y - 0.5 - filter.radius).ceil().max(self.bounds.y as f32)) as u32;
+        let x1 = ((x - 0.5 + filter.radius).floor() as i64)
+            .min((self.bounds.x + self.bounds.width) as i64 - 1);
+        let y1 = ((y - 0.5 + filter.radius).floor() as i64)
+            .min((self.bounds.y + self.bounds.height) as i64 - 1);
+
+        for py in y0 as i64..=y1 {
+            for px in x0 as i64..=x1 {
+                let weight = filter.evaluate(px as f32 + 0.5 - x, py as f32 + 0.5 - y);
+                if weight == 0.0 {
+                    continue;
+                }
+                let index =
+                    (py as u32 - self.bounds.y) * self.bounds.width + (px as u32 - self.bounds.x);
+                let splat = &mut self.splats[index as usize];
+                splat.color += weight * color;
+                splat.weight += weight;
+            }
+        }
+    }
+}
+
+// Accumulates the samples of every pixel in `region` across passes.
+pub struct Film {
+    region: Tile,
+    pixels: Vec<PixelStats>,
+    splats: Vec<Splat>,
+    // Sums of the extra layers per pixel, averaged without the filter, or the first sample of
+    // layers that aren't averaged.
+    layers: Vec<Aov>,
+    aovs: Vec<Color>,
+    // Debug layers such as normals are signed, radiance below zero is filter ringing.
+    signed: bool,
+}
+
+impl Film {
+    pub fn new(region: Tile, layers: &[Aov]) -> Self {
+        Self {
+            region,
+            pixels: vec![PixelStats::default(); region.pixels() as usize],
+            splats: vec![Splat::default(); region.pixels() as usize],
+            layers: layers.to_vec(),
+            aovs: vec![Color::zeros(); region.pixels() as usize * layers.len()],
+            signed: false,
+        }
+    }
+
+    /// Keeps negative values of the filtered image instead of clamping them to black.
+    pub fn with_signed_values(mut self) -> Self {
+        self.signed = true;
+        self
+    }
+
+    #[inline(always)]
+    fn index(&self, x: u32, y: u32) -> usize {
+        ((y - self.region.y) * self.region.width + (x - self.region.x)) as usize
+    }
+
+    /// Merges the statistics and the extra layer sums of the pixels of `tile`, given row by row.
+    pub fn merge_tile(&mut self, tile: &Tile, stats: &[PixelStats], aovs: &[Color]) {
+        for (row, stats) in stats.chunks(tile.width as usize).enumerate() {
+            let start = self.index(tile.x, tile.y + row as u32);
+            for (pixel, stats) in self.pixels[start..start + stats.len()]
+                .iter_mut()
+                .zip(stats)
+            {
+                pixel.merge(stats);
+            }
+
+            let row_aovs = tile.width as usize * self.layers.len();
+            let aovs = &aovs[row * row_aovs..(row + 1) * row_aovs];
+            let start = start * self.layers.len();
+            for (sum, value) in self.aovs[start..start + aovs.len()].iter_mut().zip(aovs) {
+                *sum += value;
+            }
+        }
+    }
+
+    /// Adds the splats of tiles that overlap each other.
+    ///
+    /// They are added in the order of their position, so the sums don't depend on which tile
+    /// finished first.
+    pub fn merge_splats(&mut self, mut tiles: Vec<SplatTile>) {
+        tiles.sort_by_key(|tile| (tile.bounds.y, tile.bounds.x));
+        for tile in tiles {
+            for (row, splats) in tile.splats.chunks(tile.bounds.width as usize).enumerate() {
+                let start = self.index(tile.bounds.x, tile.bounds.y + row as u32);
+                for (pixel, splat) in self.splats[start..start + splats.len()]
+                    .iter_mut()
+                    .zip(splats)
+                {
+                    pixel.color += splat.color;
+                    pixel.weight += splat.weight;
+                }
+            }
+        }
+    }
+
+    /// The statistics of the pixels of `tile`, row by row.
+    pub fn tile(&self, tile: &Tile) -> Vec<PixelStats> {
+        (tile.y..tile.y + tile.height)
+            .flat_map(|y| {
+                let start = self.index(tile.x, y);
+                self.pixels[start..start + tile.width as usize]
+                    .iter()
+                    .copied()
+            })
+            .collect()
+    }
+
+    /// Mean relative error over the pixels, `None` until every pixel has two samples.
+    pub fn noise(&self) -> Option<f32> {
+        let sum = self
+            .pixels
+            .iter()
+            .map(|p| p.relative_error())
+            .sum::<Option<f32>>()?;
+        Some(sum / self.pixels.len() as f32)
+    }
+
+    /// The filtered image of the pixels of `region`, placed within `frame`.
+    pub fn to_framebuffer(&self, region: &Tile, frame: &Tile) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(frame.width, frame.height);
+        let colors: Vec<Color> = self
+            .region_pixels(region, &self.splats)
+            .map(|splat| {
+                if splat.weight <= 0.0 {
+                    return Color::zeros();
+                }
+                let color = splat.color / splat.weight;
+                if self.signed {
+                    return color;
+                }
+                // Negative filter lobes can ring below black.
+                color.map(|c| c.max(0.0))
+            })
+            .collect();
+        framebuffer.write_tile(&Self::within(region, frame), &colors);
+        framebuffer
+    }
+
+    /// Extra layer `aov` of each pixel of `region`, placed within `frame`. Averaged over the
+    /// samples of the pixel if the layer [is averaged](Aov::is_averaged).
+    pub fn aov_framebuffer(&self, aov: usize, region: &Tile, frame: &Tile) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(frame.width, frame.height);
+        let colors: Vec<Color> = (region.y..region.y + region.height)
+            .flat_map(|y| {
+                let start = self.index(region.x, y);
+                start..start + region.width as usize
+            })
+            .map(|i| match self.pixels[i].samples() {
+                0 => Color::zeros(),
+                _ if !self.layers[aov].is_averaged() => self.aovs[i * self.layers.len() + aov],
+                samples => self.aovs[i * self.layers.len() + aov] / samples as f32,
+            })
+            .collect();
+        framebuffer.write_tile(&Self::within(region, frame), &colors);
+        framebuffer
+    }
+
+    /// The number of samples of each pixel of `region`, placed within `frame`.
+    pub fn sample_counts(&self, region: &Tile, frame: &Tile) -> SampleCounts {
+        let mut counts = vec![0; frame.pixels() as usize];
+        let target = Self::within(region, frame);
+        let samples: Vec<u32> = self
+            .region_pixels(region, &self.pixels)
+            .map(PixelStats::samples)
+            .collect();
+        for (row, samples) in samples.chunks(target.width as usize).enumerate() {
+            let start = ((target.y + row as u32) * frame.width + target.x) as usize;
+            counts[start..start + samples.len()].copy_from_slice(samples);
+        }
+        SampleCounts::new(frame.width, frame.height, counts)
+    }
+
+    // The values of `pixels` for `region`, row by row.
+    fn region_pixels<'a, T>(
+        &'a self,
+        region: &Tile,
+        pixels: &'a [T],
+    ) -> impl Iterator<Item = &'a T> {
+        let region = *region;
+        (region.y..region.y + region.height).flat_map(move |y| {
+            let start = self.index(region.x, y);
+            pixels[start..start + region.width as usize].iter()
+        })
+    }
+
+    // `region` relative to the top left of `frame`.
+    fn within(region: &Tile, frame: &Tile) -> Tile {
+        Tile {
+            x: region.x - frame.x,
+            y: region.y - frame.y,
+            ..*region
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterKind;
+
+    fn stats(values: &[f32]) -> PixelStats {
+        let mut stats = PixelStats::default();
+        for &value in values {
+            stats.add(Color::repeat(value));
+        }
+        stats
+    }
+
+    #[test]
+    fn merged_statistics_match_those_of_all_samples() {
+        let values: Vec<f32> = (0..100)
+            .map(|i| ((i * 37 % 101) as f32 / 10.0).powi(2))
+            .collect();
+        let all = stats(&values);
+
+        for split in [0, 1, 13, 50, 99, 100] {
+            let mut merged = stats(&values[..split]);
+            merged.merge(&stats(&values[split..]));
+            assert_eq!(merged.samples(), all.samples());
+            assert!((merged.mean - all.mean).abs() < 1e-3 * all.mean);
+            assert!((merged.m2 - all.m2).abs() < 1e-3 * all.m2);
+        }
+
+        // Merging pieces of every size in turn, as passes do.
+        let mut merged = PixelStats::default();
+        for chunk in values.chunks(7) {
+            merged.merge(&stats(chunk));
+        }
+        let (merged, all) = (merged.relative_error(), all.relative_error());
+        assert!((merged.unwrap() - all.unwrap()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn relative_error_needs_two_samples() {
+        assert_eq!(stats(&[]).relative_error(), None);
+        assert_eq!(stats(&[1.0]).relative_error(), None);
+        assert_eq!(stats(&[1.0, 1.0]).relative_error(), Some(0.0));
+        assert!(stats(&[0.0, 2.0]).relative_error().unwrap() > 0.0);
+    }
+
+    // Splat weights of the pixels of `tile` after a sample at `(x, y)`, row by row.
+    fn splat_weights(tile: Tile, filter: &Filter, x: f32, y: f32) -> Vec<f32> {
+        let mut splats = SplatTile::new(tile);
+        splats.add(filter, x, y, Color::repeat(1.0));
+        splats.splats.iter().map(|splat| splat.weight).collect()
+    }
+
+    #[test]
+    fn splats_at_a_corner_reach_the_four_pixels_around_it() {
+        let tile = Tile {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        };
+        let weights = splat_weights(tile, &Filter::new(FilterKind::Tent), 2.0, 2.0);
+        #[rustfmt::skip]
+        let expected = [
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.25, 0.25, 0.0,
+            0.0, 0.25, 0.25, 0.0,
+            0.0, 0.0, 0.0, 0.0,
+        ];
+        assert_eq!(weights, expected);
+
+        // A wider filter reaches the next ring, evenly on every side.
+        let weights = splat_weights(tile, &Filter::new(FilterKind::Mitchell), 2.0, 2.0);
+        for y in 0..2 {
+            for x in 0..2 {
+                let weight = weights[y * 4 + x];
+                assert!(weight != 0.0);
+                for mirrored in [y * 4 + 3 - x, (3 - y) * 4 + x, (3 - y) * 4 + 3 - x] {
+                    assert!((weights[mirrored] - weight).abs() < 1e-6);
+                }
+            }
+        }
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn splats_stay_within_the_tile_bounds() {
+        let tile = Tile {
+            x: 2,
+            y: 1,
+            width: 2,
+            height: 2,
+        };
+        let weights = splat_weights(tile, &Filter::new(FilterKind::Tent), 2.0, 2.0);
+        assert_eq!(weights, [0.25, 0.0, 0.25, 0.0]);
+    }
+}
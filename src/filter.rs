@@ -0,0 +1,172 @@
+use clap::ValueEnum;
+use std::f32::consts::PI;
+
+// Integrals of the Gaussian and Lanczos filters of radius one over `[-1, 1]`, other radii scale them.
+const GAUSSIAN_INTEGRAL: f32 = 0.811_069;
+const LANCZOS_INTEGRAL: f32 = 0.504_895;
+
+/// The shape of the reconstruction filter, how much a sample counts towards the pixels around it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum FilterKind {
+    /// Every sample within the radius counts the same, a plain average for the default radius.
+    #[default]
+    Box,
+    /// Falls off linearly with the distance.
+    Tent,
+    /// Smooth, slightly blurry.
+    Gaussian,
+    /// Mitchell–Netravali with B = C = 1/3, sharp with little ringing.
+    Mitchell,
+    /// Windowed sinc, the sharpest, rings around high contrast edges.
+    Lanczos,
+}
+
+impl FilterKind {
+    pub fn default_radius(&self) -> f32 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 2.0,
+        }
+    }
+}
+
+/// A separable reconstruction filter reaching `radius` pixels from a sample on both axes.
+#[derive(Copy, Clone, Debug)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub radius: f32,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(FilterKind::default())
+    }
+}
+
+impl Filter {
+    /// A filter of `kind` with its usual radius.
+    pub fn new(kind: FilterKind) -> Self {
+        Self {
+            kind,
+            radius: kind.default_radius(),
+        }
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Pixels around a pixel that its samples reach.
+    pub fn margin(&self) -> u32 {
+        (self.radius - 0.5).ceil().max(0.0) as u32
+    }
+
+    /// Weight of a sample `(dx, dy)` pixels from the centre of a pixel, integrating to one over
+    /// the square within the radius. May be negative.
+    #[inline(always)]
+    pub fn evaluate(&self, dx: f32, dy: f32) -> f32 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    #[inline(always)]
+    fn evaluate_1d(&self, d: f32) -> f32 {
+        let (d, radius) = (d.abs(), self.radius);
+        if d >= radius {
+            return 0.0;
+        }
+        match self.kind {
+            FilterKind::Box => 1.0 / (2.0 * radius),
+            FilterKind::Tent => (radius - d) / (radius * radius),
+            FilterKind::Gaussian => {
+                // Shifted down so the filter reaches zero at the radius.
+                let sigma = radius / 3.0;
+                (gaussian(d, sigma) - gaussian(radius, sigma)) / (GAUSSIAN_INTEGRAL * radius)
+            }
+            // Integrates to one over `[-2, 2]`.
+            FilterKind::Mitchell => mitchell(2.0 * d / radius) * 2.0 / radius,
+            FilterKind::Lanczos => {
+                let d = 2.0 * d / radius;
+                sinc(d) * sinc(d / 2.0) / (LANCZOS_INTEGRAL * radius)
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn gaussian(d: f32, sigma: f32) -> f32 {
+    (-d * d / (2.0 * sigma * sigma)).exp()
+}
+
+// Mitchell–Netravali on `[0, 2]` with B = C = 1/3.
+#[inline(always)]
+fn mitchell(x: f32) -> f32 {
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+    let x2 = x * x;
+    let x3 = x2 * x;
+    if x < 1.0 {
+        ((12.0 - 9.0 * B - 6.0 * C) * x3 + (-18.0 + 12.0 * B + 6.0 * C) * x2 + (6.0 - 2.0 * B))
+            / 6.0
+    } else {
+        ((-B - 6.0 * C) * x3
+            + (6.0 * B + 30.0 * C) * x2
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0
+    }
+}
+
+#[inline(always)]
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    let x = PI * x;
+    x.sin() / x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_integrate_to_one() {
+        const STEPS: usize = 400;
+        for kind in FilterKind::value_variants() {
+            for radius in [0.5, kind.default_radius(), 3.0] {
+                let filter = Filter::new(*kind).with_radius(radius);
+                let step = 2.0 * radius / STEPS as f32;
+                let mut integral = 0.0;
+                for i in 0..STEPS {
+                    for j in 0..STEPS {
+                        let dx = -radius + (i as f32 + 0.5) * step;
+                        let dy = -radius + (j as f32 + 0.5) * step;
+                        integral += filter.evaluate(dx, dy) * step * step;
+                    }
+                }
+                assert!(
+                    (integral - 1.0).abs() < 1e-3,
+                    "{:?} of radius {} integrates to {}",
+                    kind,
+                    radius,
+                    integral
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn filters_vanish_beyond_their_radius() {
+        for kind in FilterKind::value_variants() {
+            let filter = Filter::new(*kind);
+            let radius = filter.radius;
+            assert_eq!(filter.evaluate(radius, 0.0), 0.0);
+            assert_eq!(filter.evaluate(0.0, -radius - 0.1), 0.0);
+            assert!(filter.evaluate(0.0, 0.0) > 0.0);
+        }
+    }
+}
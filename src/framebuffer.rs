@@ -0,0 +1,65 @@
+use image::{ImageBuffer, Rgb, Rgb32FImage};
+
+use crate::{tiles::Tile, vec::Color};
+
+// Linear radiance per pixel, stored row by row from the top of the image.
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::from_pixels(
+            width,
+            height,
+            vec![Color::zeros(); (width * height) as usize],
+        )
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (width * height) as usize,
+            "One colour per pixel"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    #[inline(always)]
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Copies the pixels of `tile`, given row by row, into place.
+    pub fn write_tile(&mut self, tile: &Tile, pixels: &[Color]) {
+        for (row, colors) in pixels.chunks(tile.width as usize).enumerate() {
+            let start = ((tile.y + row as u32) * self.width + tile.x) as usize;
+            self.pixels[start..start + colors.len()].copy_from_slice(colors);
+        }
+    }
+
+    pub fn to_rgb32f(&self) -> Rgb32FImage {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let c = self.get(x, y);
+            Rgb([c.x, c.y, c.z])
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+
+use crate::vec::Color;
+
+// Stops of the heatmap gradient, from no samples to the most samples of any pixel.
+const GRADIENT: [[f32; 3]; 5] = [
+    [0.0, 0.0, 0.0],
+    [0.2, 0.1, 0.6],
+    [0.8, 0.2, 0.4],
+    [1.0, 0.7, 0.1],
+    [1.0, 1.0, 1.0],
+];
+
+/// Samples taken in each pixel of a render, row by row from the top of the image.
+pub struct SampleCounts {
+    width: u32,
+    height: u32,
+    counts: Vec<u32>,
+}
+
+impl SampleCounts {
+    pub fn new(width: u32, height: u32, counts: Vec<u32>) -> Self {
+        assert_eq!(
+            counts.len(),
+            (width * height) as usize,
+            "One count per pixel"
+        );
+        Self {
+            width,
+            height,
+            counts,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    #[inline(always)]
+    pub fn get(&self, x: u32, y: u32) -> u32 {
+        self.counts[(y * self.width + x) as usize]
+    }
+
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn mean(&self) -> f32 {
+        self.counts.iter().map(|&c| c as f64).sum::<f64>() as f32 / self.counts.len() as f32
+    }
+}
+
+/// Colours every pixel by its sample count relative to the busiest pixel, black for none and
+/// white for the most.
+pub fn heatmap(counts: &SampleCounts) -> RgbImage {
+    let max = counts.max().max(1) as f32;
+    ImageBuffer::from_fn(counts.width(), counts.height(), |x, y| {
+        let c = gradient(counts.get(x, y) as f32 / max).map(|c| (c * 255.0).round() as u8);
+        Rgb([c.x, c.y, c.z])
+    })
+}
+
+pub(crate) fn gradient(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0) * (GRADIENT.len() - 1) as f32;
+    let i = (t as usize).min(GRADIENT.len() - 2);
+    let (a, b) = (Color::from(GRADIENT[i]), Color::from(GRADIENT[i + 1]));
+    a.lerp(&b, t - i as f32)
+}
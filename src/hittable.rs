@@ -1,8 +1,21 @@
 pub mod aabb;
 pub mod bvh;
+pub mod cuboid;
+pub mod disk;
+pub mod instance;
+pub mod moving_sphere;
+pub mod plane;
+pub mod quad;
 pub mod sphere;
+pub mod triangle;
 
-use crate::{materials::Material, ray::Ray, vec::Vec3};
+use std::sync::Arc;
+
+use crate::{
+    materials::Material,
+    ray::Ray,
+    vec::{Vec2, Vec3},
+};
 
 use self::aabb::Aabb;
 
@@ -10,10 +23,116 @@ pub struct HitRecord<'a> {
     pub point: Vec3,
     pub normal: Vec3,
     pub t: f32,
+    pub u: f32,
+    pub v: f32,
     pub material: &'a dyn Material,
 }
 
-pub trait Hittable: Sync {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>>;
+    // Bounds over the whole time range of a moving hittable, so the BVH holds it at any time.
     fn bounding_box(&self) -> Option<Aabb>;
+
+    // Solid angle density of `random_direction` from `origin` at `time`, used when the hittable is
+    // a light.
+    fn pdf_value(&self, _origin: &Vec3, _direction: &Vec3, _time: f32) -> f32 {
+        0.0
+    }
+
+    // The material of a hittable made of a single one, for numbering the materials of a scene.
+    fn material(&self) -> Option<&dyn Material> {
+        None
+    }
+
+    // Calls `f` with the materials of the hittable, those of everything inside it for BVHs and
+    // instances. A material may be visited more than once.
+    fn for_each_material(&self, f: &mut dyn FnMut(&dyn Material)) {
+        if let Some(material) = self.material() {
+            f(material);
+        }
+    }
+
+    // Direction towards a point of the hittable picked with the uniform sample `u`.
+    fn random_direction(&self, _origin: &Vec3, _time: f32, _u: Vec2) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+// Lets BVHs hold hittables borrowed, boxed or shared.
+macro_rules! forward_hittable {
+    ($($pointer:ty),*) => {$(
+        impl<H: Hittable + ?Sized> Hittable for $pointer {
+            #[inline(always)]
+            fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+                (**self).hit(ray, t_min, t_max)
+            }
+
+            fn bounding_box(&self) -> Option<Aabb> {
+                (**self).bounding_box()
+            }
+
+            fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+                (**self).pdf_value(origin, direction, time)
+            }
+
+            fn material(&self) -> Option<&dyn Material> {
+                (**self).material()
+            }
+
+            fn for_each_material(&self, f: &mut dyn FnMut(&dyn Material)) {
+                (**self).for_each_material(f)
+            }
+
+            fn random_direction(&self, origin: &Vec3, time: f32, u: Vec2) -> Vec3 {
+                (**self).random_direction(origin, time, u)
+            }
+        }
+    )*};
+}
+
+forward_hittable!(&H, Box<H>, Arc<H>);
+
+// Checks the directions `light` samples from `origin` against its `pdf_value`: the density
+// integrates to one over the sphere of directions, and `1 / pdf` averaged over sampled directions
+// is the solid angle the light covers.
+#[cfg(test)]
+pub(crate) fn assert_light_sampling(light: &dyn Hittable, origin: Vec3, time: f32) {
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use std::f64::consts::PI;
+
+    const SAMPLES: usize = 100_000;
+    let mut rng = SmallRng::seed_from_u64(1);
+
+    let (mut integral, mut covered) = (0.0, 0);
+    for _ in 0..SAMPLES {
+        let z: f32 = rng.gen_range(-1.0..1.0);
+        let phi: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+        let r = (1.0 - z * z).sqrt();
+        let pdf = light.pdf_value(&origin, &Vec3::new(r * phi.cos(), r * phi.sin(), z), time);
+        integral += pdf as f64;
+        covered += (pdf > 0.0) as usize;
+    }
+    let integral = integral * 4.0 * PI / SAMPLES as f64;
+    let solid_angle = covered as f64 * 4.0 * PI / SAMPLES as f64;
+    assert!(
+        (integral - 1.0).abs() < 0.03,
+        "pdf integrates to {}",
+        integral
+    );
+
+    let mut inverse_pdfs = 0.0;
+    for _ in 0..SAMPLES {
+        let u = Vec2::new(rng.gen(), rng.gen());
+        let direction = light.random_direction(&origin, time, u);
+        let pdf = light.pdf_value(&origin, &direction, time);
+        assert!(pdf > 0.0, "sampled {:?} has no density", direction);
+        inverse_pdfs += 1.0 / pdf as f64;
+    }
+    let sampled = inverse_pdfs / SAMPLES as f64;
+    assert!(
+        (sampled / solid_angle - 1.0).abs() < 0.03,
+        "samples cover {} steradians, not {}",
+        sampled,
+        solid_angle
+    );
 }
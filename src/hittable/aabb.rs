@@ -1,4 +1,8 @@
-use crate::{ray::Ray, vec::Vec3};
+use crate::vec::Vec3;
+
+// Minimum thickness of the boxes of flat primitives relative to their distance from the origin,
+// so the slabs of `Aabb::hit` don't collapse to nothing in floating point.
+pub const BOX_PADDING: f32 = 1e-4;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Aabb {
@@ -7,10 +11,11 @@ pub struct Aabb {
 }
 
 impl Aabb {
-    pub fn hit(&self, r: &Ray, mut t_min: f32, mut t_max: f32) -> bool {
+    #[inline(always)]
+    pub fn hit(&self, origin: &Vec3, inv_direction: &Vec3, mut t_min: f32, mut t_max: f32) -> bool {
         for a in 0..3 {
-            let mint = (self.min[a] - r.origin()[a]) / r.direction()[a];
-            let maxt = (self.max[a] - r.origin()[a]) / r.direction()[a];
+            let mint = (self.min[a] - origin[a]) * inv_direction[a];
+            let maxt = (self.max[a] - origin[a]) * inv_direction[a];
             let t0 = mint.min(maxt);
             let t1 = mint.max(maxt);
 
@@ -24,6 +29,29 @@ impl Aabb {
 
         true
     }
+
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Grows the box to at least `delta` times `1 + |coordinate|` along each axis.
+    pub fn padded(&self, delta: f32) -> Aabb {
+        let mut padded = *self;
+        for a in 0..3 {
+            let delta = delta * (1.0 + padded.min[a].abs().max(padded.max[a].abs()));
+            if padded.max[a] - padded.min[a] < delta {
+                padded.min[a] -= delta / 2.0;
+                padded.max[a] += delta / 2.0;
+            }
+        }
+
+        padded
+    }
 }
 
 pub fn surrounding_box(box0: &Aabb, box1: &Aabb) -> Aabb {
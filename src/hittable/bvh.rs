@@ -1,226 +1,709 @@
 use crate::hittable::aabb::{surrounding_box, Aabb};
+use crate::materials::Material;
+use crate::vec::Vec3;
 use crate::Ray;
-use rand::Rng;
-use std::cmp::Ordering;
-use std::fmt;
+use anyhow::{anyhow, Result};
+use std::{cell::Cell, fmt};
 
 use super::{HitRecord, Hittable};
 
-pub struct BvhTree<'a> {
-    nodes: Vec<BvhNode<'a>>,
-    root: NodeId,
+#[derive(Copy, Clone, Debug)]
+pub struct BvhConfig {
+    pub max_leaf_size: usize,
+    pub bins: usize,
+    pub traversal_cost: f32,
+    pub intersection_cost: f32,
 }
 
-struct BvhNode<'a> {
-    left: Option<NodeId>,
-    right: Option<NodeId>,
-    aabb: Option<Aabb>,
-    hittable: Option<&'a Box<dyn Hittable>>,
+impl Default for BvhConfig {
+    fn default() -> Self {
+        Self {
+            max_leaf_size: 4,
+            bins: 16,
+            traversal_cost: 0.5,
+            intersection_cost: 1.0,
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct NodeId {
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BvhStats {
+    pub hittables: usize,
+    /// Hittables without finite bounds, tested by every ray next to the tree.
+    pub unbounded: usize,
+    pub nodes: usize,
+    pub leaves: usize,
+    pub depth: usize,
+    pub sah_cost: f32,
+}
+
+const MAX_DEPTH: usize = 64;
+
+/// Work done by [`Bvh::hit`] calls, including those of nested BVHs.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraversalCounts {
+    pub aabb_tests: u32,
+    pub primitive_tests: u32,
+}
+
+thread_local! {
+    // Set while `count_traversals` runs, so other traversals skip the bookkeeping.
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static TRAVERSAL: Cell<TraversalCounts> = const {
+        Cell::new(TraversalCounts {
+            aabb_tests: 0,
+            primitive_tests: 0,
+        })
+    };
+}
+
+/// Runs `f`, returning the work done by the BVH traversals it made on the current thread.
+pub fn count_traversals<T>(f: impl FnOnce() -> T) -> (T, TraversalCounts) {
+    let counting = COUNTING.replace(true);
+    let outer = TRAVERSAL.take();
+    let result = f();
+    let counts = TRAVERSAL.replace(outer);
+    COUNTING.set(counting);
+    if counting {
+        add_traversal_counts(counts);
+    }
+    (result, counts)
+}
+
+/// A bounding volume hierarchy over hittables of type `H`.
+///
+/// BVHs nest: a mesh can be built into a bottom-level `Bvh<Triangle>` once and shared between
+/// [`Instance`](super::instance::Instance)s, which a top-level BVH over the scene's objects holds.
+/// Only the top level needs rebuilding when instances move.
+pub struct Bvh<H: Hittable> {
+    // Empty when there are no bounded hittables.
+    nodes: Vec<LinearNode>,
+    hittables: Vec<H>,
+    unbounded: Vec<H>,
+    stats: BvhStats,
+}
+
+/// The top level of a render, borrowing the scene's objects.
+pub type BvhTree<'a> = Bvh<&'a dyn Hittable>;
+
+// Nodes are laid out depth-first, so the first child of an interior node directly follows it.
+struct LinearNode {
+    aabb: Aabb,
+    // First hittable of a leaf, or the index of the second child of an interior node.
+    offset: u32,
+    // Number of hittables in a leaf, zero for interior nodes.
+    count: u16,
+    axis: u8,
+}
+
+struct BuildPrimitive {
     index: usize,
+    aabb: Aabb,
+    centroid: Vec3,
 }
 
-impl<'a> BvhTree<'a> {
-    fn hit(&self, id: NodeId, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let node = &self.nodes[id.index];
+#[derive(Copy, Clone)]
+struct Bin {
+    aabb: Option<Aabb>,
+    count: usize,
+}
 
-        if node.aabb.is_none() || node.aabb.is_some() && node.aabb.unwrap().hit(r, t_min, t_max) {
-            match node.hittable {
-                Some(ref hittable) => return hittable.hit(r, t_min, t_max),
-                None => {}
-            }
+struct Split {
+    axis: usize,
+    bin: usize,
+    cost: f32,
+}
 
-            let mut hit_left: Option<HitRecord> = None;
-            let mut hit_right: Option<HitRecord> = None;
+impl<H: Hittable> Hittable for Bvh<H> {
+    fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|node| node.aabb)
+    }
 
-            if let Some(ref left_index) = node.left {
-                hit_left = self.hit(*left_index, r, t_min, t_max);
-            }
+    fn for_each_material(&self, f: &mut dyn FnMut(&dyn Material)) {
+        // Neighbouring hittables mostly share a material, all the triangles of a mesh do.
+        let mut last = None;
+        for hittable in self.hittables.iter().chain(&self.unbounded) {
+            hittable.for_each_material(&mut |material| {
+                if last != Some(material.identity()) {
+                    last = Some(material.identity());
+                    f(material);
+                }
+            });
+        }
+    }
+
+    fn hit(&self, r: &Ray, t_min: f32, mut t_max: f32) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        let mut counts = TraversalCounts::default();
 
-            if let Some(ref right_index) = node.right {
-                hit_right = self.hit(*right_index, r, t_min, t_max);
+        counts.primitive_tests += self.unbounded.len() as u32;
+        for hittable in &self.unbounded {
+            if let Some(record) = hittable.hit(r, t_min, t_max) {
+                t_max = record.t;
+                closest = Some(record);
             }
+        }
+        if self.nodes.is_empty() {
+            add_traversal_counts(counts);
+            return closest;
+        }
 
-            match hit_left {
-                Some(ref left) => match hit_right {
-                    Some(ref right) => {
-                        if left.t < right.t {
-                            return hit_left;
-                        } else {
-                            return hit_right;
-                        }
+        let origin = r.origin();
+        let inv_direction = r.direction().map(|d| 1.0 / d);
+        let dir_is_neg = [
+            inv_direction.x < 0.0,
+            inv_direction.y < 0.0,
+            inv_direction.z < 0.0,
+        ];
+
+        let mut stack = [0u32; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            counts.aabb_tests += 1;
+            if node.aabb.hit(&origin, &inv_direction, t_min, t_max) {
+                if node.count == 0 {
+                    // Descend into the near child first so its hits narrow `t_max` for the far one.
+                    let (near, far) = if dir_is_neg[node.axis as usize] {
+                        (node.offset, current as u32 + 1)
+                    } else {
+                        (current as u32 + 1, node.offset)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near as usize;
+                    continue;
+                }
+
+                let first = node.offset as usize;
+                counts.primitive_tests += node.count as u32;
+                for hittable in &self.hittables[first..first + node.count as usize] {
+                    if let Some(record) = hittable.hit(r, t_min, t_max) {
+                        t_max = record.t;
+                        closest = Some(record);
                     }
-                    None => return hit_left,
-                },
-                None => {}
+                }
             }
 
-            match hit_right {
-                Some(_) => return hit_right,
-                None => {}
+            if stack_len == 0 {
+                add_traversal_counts(counts);
+                return closest;
             }
+            stack_len -= 1;
+            current = stack[stack_len] as usize;
         }
-
-        None
     }
 }
 
-impl<'a> Hittable for BvhTree<'a> {
-    fn bounding_box(&self) -> Option<Aabb> {
-        self.nodes[self.root.index].aabb
+#[inline(always)]
+fn add_traversal_counts(counts: TraversalCounts) {
+    if !COUNTING.get() {
+        return;
     }
+    TRAVERSAL.with(|total| {
+        let total_counts = total.get();
+        total.set(TraversalCounts {
+            aabb_tests: total_counts.aabb_tests.wrapping_add(counts.aabb_tests),
+            primitive_tests: total_counts
+                .primitive_tests
+                .wrapping_add(counts.primitive_tests),
+        })
+    });
+}
 
-    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        self.hit(self.root, r, t_min, t_max)
+impl<H: Hittable> Bvh<H> {
+    pub fn new(hittables: Vec<H>) -> Result<Self> {
+        Self::with_config(hittables, BvhConfig::default())
     }
-}
 
-impl<'a> BvhTree<'a> {
-    pub fn new(l: &'a mut [Box<dyn Hittable>]) -> BvhTree<'a> {
-        let mut tree = BvhTree {
-            nodes: Vec::new(),
-            root: NodeId { index: 0 },
+    /// Builds the tree over the hittables with finite bounds, the others are kept in a list that
+    /// every ray is tested against. Fails on bounds containing NaN.
+    pub fn with_config(hittables: Vec<H>, config: BvhConfig) -> Result<Self> {
+        if config.max_leaf_size > u16::MAX as usize {
+            return Err(anyhow!(
+                "BVH leaf size {} is larger than {}",
+                config.max_leaf_size,
+                u16::MAX
+            ));
+        }
+
+        let mut primitives = Vec::with_capacity(hittables.len());
+        let mut bounded = Vec::with_capacity(hittables.len());
+        let mut unbounded = Vec::new();
+        for (index, hittable) in hittables.into_iter().enumerate() {
+            let Some(aabb) = hittable.bounding_box() else {
+                unbounded.push(hittable);
+                continue;
+            };
+            let corners = aabb.min.iter().chain(aabb.max.iter());
+            if corners.clone().any(|c| c.is_nan()) {
+                return Err(anyhow!("object {} has NaN in its bounding box", index));
+            }
+            if !corners.clone().all(|c| c.is_finite()) {
+                unbounded.push(hittable);
+                continue;
+            }
+            primitives.push(BuildPrimitive {
+                index: bounded.len(),
+                aabb,
+                centroid: aabb.centroid(),
+            });
+            bounded.push(Some(hittable));
+        }
+
+        let mut builder = Builder {
+            config,
+            nodes: Vec::with_capacity(2 * primitives.len()),
+            order: Vec::with_capacity(primitives.len()),
+            depth: 0,
         };
-        tree.root = tree.build(l);
+        if !primitives.is_empty() {
+            builder.build(&mut primitives, 1);
+        }
 
-        tree
+        // Leaves refer to ranges of the hittables in the order the builder left them.
+        let mut tree = Bvh {
+            nodes: builder.nodes,
+            hittables: builder
+                .order
+                .iter()
+                .map(|&index| bounded[index].take().unwrap())
+                .collect(),
+            unbounded,
+            stats: BvhStats::default(),
+        };
+        tree.stats = BvhStats {
+            hittables: tree.hittables.len(),
+            unbounded: tree.unbounded.len(),
+            nodes: tree.nodes.len(),
+            leaves: tree.nodes.iter().filter(|node| node.count > 0).count(),
+            depth: builder.depth,
+            sah_cost: tree.sah_cost(&config),
+        };
+
+        Ok(tree)
     }
 
-    fn build(&mut self, l: &'a mut [Box<dyn Hittable>]) -> NodeId {
-        let axis: i32 = rand::thread_rng().gen_range(0..3);
+    /// Calls `f` with every hittable whose bounds `ray` passes through, unbounded ones included.
+    pub fn for_each_candidate(&self, ray: &Ray, mut f: impl FnMut(&H)) {
+        self.unbounded.iter().for_each(&mut f);
+        if self.nodes.is_empty() {
+            return;
+        }
 
-        match axis {
-            0 => l.sort_by(|a, b| box_x_compare(a, b)),
-            1 => l.sort_by(|a, b| box_y_compare(a, b)),
-            2 => l.sort_by(|a, b| box_z_compare(a, b)),
-            _ => panic!("Unexpected axis"),
+        let origin = ray.origin();
+        let inv_direction = ray.direction().map(|d| 1.0 / d);
+        let mut stack = [0u32; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if node.aabb.hit(&origin, &inv_direction, 0.0, f32::INFINITY) {
+                if node.count == 0 {
+                    stack[stack_len] = node.offset;
+                    stack_len += 1;
+                    current += 1;
+                    continue;
+                }
+                let first = node.offset as usize;
+                self.hittables[first..first + node.count as usize]
+                    .iter()
+                    .for_each(&mut f);
+            }
+
+            if stack_len == 0 {
+                return;
+            }
+            stack_len -= 1;
+            current = stack[stack_len] as usize;
         }
+    }
 
-        let left: NodeId;
-        let right: NodeId;
+    fn sah_cost(&self, config: &BvhConfig) -> f32 {
+        let Some(root) = self.nodes.first() else {
+            return 0.0;
+        };
+        let root_area = root.aabb.surface_area();
+        if root_area <= 0.0 {
+            return 0.0;
+        }
 
-        if l.len() == 1 {
-            return self.new_leaf(&l[0]);
-        } else if l.len() == 2 {
-            left = self.new_leaf(&l[0]);
-            right = self.new_leaf(&l[1]);
-        } else {
-            let half_len = l.len() / 2;
-            let (left_hittables, right_hittables) = l.split_at_mut(half_len);
+        self.nodes
+            .iter()
+            .map(|node| {
+                let relative_area = node.aabb.surface_area() / root_area;
+                if node.count > 0 {
+                    relative_area * config.intersection_cost * node.count as f32
+                } else {
+                    relative_area * config.traversal_cost
+                }
+            })
+            .sum()
+    }
+}
+
+struct Builder {
+    config: BvhConfig,
+    nodes: Vec<LinearNode>,
+    // Indices of the bounded hittables, leaf by leaf.
+    order: Vec<usize>,
+    depth: usize,
+}
+
+impl Builder {
+    fn build(&mut self, primitives: &mut [BuildPrimitive], depth: usize) {
+        self.depth = self.depth.max(depth);
 
-            left = self.build(left_hittables);
-            right = self.build(right_hittables);
+        let aabb = primitives[1..]
+            .iter()
+            .fold(primitives[0].aabb, |acc, p| surrounding_box(&acc, &p.aabb));
+        // Leaves are forced at the maximum depth so traversal never overflows its stack.
+        if primitives.len() == 1 || depth == MAX_DEPTH {
+            return self.new_leaf(aabb, primitives);
         }
 
-        if let Some(left_box) = self.nodes[left.index].aabb {
-            if let Some(right_box) = self.nodes[right.index].aabb {
-                return self.new_node(
-                    surrounding_box(&left_box, &right_box),
-                    Some(left),
-                    Some(right),
-                );
-            }
+        let centroid_bounds = primitives[1..].iter().fold(
+            Aabb {
+                min: primitives[0].centroid,
+                max: primitives[0].centroid,
+            },
+            |acc, p| Aabb {
+                min: acc.min.inf(&p.centroid),
+                max: acc.max.sup(&p.centroid),
+            },
+        );
+
+        // An uneven split could leave more hittables than a leaf holds at the maximum depth, the
+        // hittables are halved instead once the levels left only just fit them.
+        if primitives.len() - 1 > max_subtree_size(depth + 1) {
+            let axis = (centroid_bounds.max - centroid_bounds.min).imax();
+            let mid = primitives.len() / 2;
+            primitives
+                .select_nth_unstable_by(mid, |a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+            return self.new_interior(aabb, axis, primitives.split_at_mut(mid), depth);
         }
 
-        panic!("No bounding box in BvhNode::build");
-    }
+        let (axis, mid) = match self.find_split(primitives, &aabb, &centroid_bounds) {
+            Some(split) => {
+                let leaf_cost = self.config.intersection_cost * primitives.len() as f32;
+                if primitives.len() <= self.config.max_leaf_size && leaf_cost <= split.cost {
+                    return self.new_leaf(aabb, primitives);
+                }
+                let mid = partition(primitives, |p| {
+                    self.bin_index(p, &centroid_bounds, split.axis) <= split.bin
+                });
+                if mid == 0 || mid == primitives.len() {
+                    (split.axis, primitives.len() / 2)
+                } else {
+                    (split.axis, mid)
+                }
+            }
+            // All centroids coincide, so no split can separate them by position.
+            None => {
+                if primitives.len() <= self.config.max_leaf_size {
+                    return self.new_leaf(aabb, primitives);
+                }
+                (0, primitives.len() / 2)
+            }
+        };
 
-    fn new_leaf(&mut self, hittable: &'a Box<dyn Hittable>) -> NodeId {
-        let next_index = self.nodes.len();
+        self.new_interior(aabb, axis, primitives.split_at_mut(mid), depth);
+    }
 
-        self.nodes.push(BvhNode {
-            left: None,
-            right: None,
-            aabb: hittable.bounding_box(),
-            hittable: Some(hittable),
+    fn new_interior(
+        &mut self,
+        aabb: Aabb,
+        axis: usize,
+        (left, right): (&mut [BuildPrimitive], &mut [BuildPrimitive]),
+        depth: usize,
+    ) {
+        let node = self.nodes.len();
+        self.nodes.push(LinearNode {
+            aabb,
+            offset: 0,
+            count: 0,
+            axis: axis as u8,
         });
 
-        return NodeId { index: next_index };
+        self.build(left, depth + 1);
+        self.nodes[node].offset = self.nodes.len() as u32;
+        self.build(right, depth + 1);
     }
 
-    fn new_node(&mut self, aabb: Aabb, left: Option<NodeId>, right: Option<NodeId>) -> NodeId {
-        let next_index = self.nodes.len();
+    fn find_split(
+        &self,
+        primitives: &[BuildPrimitive],
+        aabb: &Aabb,
+        centroid_bounds: &Aabb,
+    ) -> Option<Split> {
+        let bins = self.config.bins.max(2);
+        let area = aabb.surface_area();
+        let mut best: Option<Split> = None;
+
+        for axis in 0..3 {
+            if centroid_bounds.max[axis] <= centroid_bounds.min[axis] {
+                continue;
+            }
+
+            let mut binned = vec![
+                Bin {
+                    aabb: None,
+                    count: 0
+                };
+                bins
+            ];
+            for p in primitives {
+                let bin = &mut binned[self.bin_index(p, centroid_bounds, axis)];
+                bin.count += 1;
+                bin.aabb = Some(match bin.aabb {
+                    Some(ref b) => surrounding_box(b, &p.aabb),
+                    None => p.aabb,
+                });
+            }
 
-        self.nodes.push(BvhNode {
-            left,
-            right,
-            aabb: Some(aabb),
-            hittable: None,
+            // Sweep from the right to get the cost of everything above each split plane.
+            let mut right_costs = vec![0.0; bins];
+            let mut accumulated = Bin {
+                aabb: None,
+                count: 0,
+            };
+            for i in (1..bins).rev() {
+                accumulated = merge(&accumulated, &binned[i]);
+                right_costs[i - 1] = bin_cost(&accumulated);
+            }
+
+            let mut accumulated = Bin {
+                aabb: None,
+                count: 0,
+            };
+            for (i, right_cost) in right_costs.iter().enumerate().take(bins - 1) {
+                accumulated = merge(&accumulated, &binned[i]);
+                let cost = self.config.traversal_cost
+                    + self.config.intersection_cost * (bin_cost(&accumulated) + right_cost)
+                        / area.max(f32::MIN_POSITIVE);
+                if !matches!(best, Some(ref b) if b.cost <= cost) {
+                    best = Some(Split { axis, bin: i, cost });
+                }
+            }
+        }
+
+        best
+    }
+
+    #[inline(always)]
+    fn bin_index(&self, p: &BuildPrimitive, centroid_bounds: &Aabb, axis: usize) -> usize {
+        let bins = self.config.bins.max(2);
+        let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+        let relative = (p.centroid[axis] - centroid_bounds.min[axis]) / extent;
+
+        ((relative * bins as f32) as usize).min(bins - 1)
+    }
+
+    fn new_leaf(&mut self, aabb: Aabb, primitives: &[BuildPrimitive]) {
+        let first = self.order.len();
+        self.order.extend(primitives.iter().map(|p| p.index));
+
+        self.nodes.push(LinearNode {
+            aabb,
+            offset: first as u32,
+            count: primitives.len() as u16,
+            axis: 0,
         });
+    }
+}
 
-        return NodeId { index: next_index };
+// The most hittables a node at `depth` can hold when every level below it halves them.
+fn max_subtree_size(depth: usize) -> usize {
+    let size = (u16::MAX as u128) << (MAX_DEPTH - depth.min(MAX_DEPTH));
+    size.min(usize::MAX as u128) as usize
+}
+
+fn merge(a: &Bin, b: &Bin) -> Bin {
+    Bin {
+        aabb: match (a.aabb, b.aabb) {
+            (Some(a), Some(b)) => Some(surrounding_box(&a, &b)),
+            (a, b) => a.or(b),
+        },
+        count: a.count + b.count,
     }
+}
 
-    fn number_hittables(&self, id: NodeId) -> usize {
-        let node = &self.nodes[id.index];
-        let local_hittable = if node.hittable.is_some() { 1 } else { 0 };
-        let count_left = if let Some(left_index) = node.left {
-            self.number_hittables(left_index)
-        } else {
-            0
-        };
-        let count_right = if let Some(right_index) = node.right {
-            self.number_hittables(right_index)
-        } else {
-            0
-        };
+fn bin_cost(bin: &Bin) -> f32 {
+    bin.aabb.map_or(0.0, |aabb| aabb.surface_area()) * bin.count as f32
+}
 
-        local_hittable + count_left + count_right
+fn partition<T>(items: &mut [T], pred: impl Fn(&T) -> bool) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if pred(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
     }
+
+    mid
 }
 
-impl<'a> fmt::Display for BvhTree<'a> {
+impl<H: Hittable> fmt::Display for Bvh<H> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "BVH with {:?} hittables and {:?} nodes",
-            self.number_hittables(self.root),
-            self.nodes.len()
-        )
+            "BVH with {:?} hittables and {:?} nodes ({} leaves, depth {}, SAH cost {:.2})",
+            self.stats.hittables,
+            self.stats.nodes,
+            self.stats.leaves,
+            self.stats.depth,
+            self.stats.sah_cost
+        )?;
+        if self.stats.unbounded > 0 {
+            write!(f, ", {} unbounded", self.stats.unbounded)?;
+        }
+        Ok(())
     }
 }
 
-fn box_x_compare(a: &Box<dyn Hittable>, b: &Box<dyn Hittable>) -> Ordering {
-    if let Some(box_left) = a.bounding_box() {
-        if let Some(box_right) = b.bounding_box() {
-            if let Some(cmp) = box_left.min.x.partial_cmp(&box_right.min.x) {
-                return cmp;
-            } else {
-                panic!("Can't compare");
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        hittable::{plane::Plane, sphere::Sphere},
+        materials::{lambertian::Lambertian, Material},
+        vec::{random_vec, Color},
+    };
+
+    fn spheres(rng: &mut SmallRng, count: usize) -> Vec<Box<dyn Hittable>> {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::repeat(0.5)));
+        (0..count)
+            .map(|_| {
+                let sphere = Sphere::new(
+                    random_vec(rng, -10.0..10.0),
+                    rng.gen_range(0.05..1.0),
+                    Arc::clone(&material),
+                );
+                Box::new(sphere) as Box<dyn Hittable>
+            })
+            .collect()
     }
 
-    panic!("No bounding box in BvhNode::new");
-}
+    // Checks `bvh` finds the same nearest hits as testing every hittable of `all`.
+    fn assert_matches_brute_force(bvh: &dyn Hittable, all: &[&dyn Hittable], rng: &mut SmallRng) {
+        for _ in 0..2000 {
+            let ray = Ray::new(random_vec(rng, -12.0..12.0), random_vec(rng, -1.0..1.0));
+            let expected = all
+                .iter()
+                .filter_map(|h| h.hit(&ray, 0.001, f32::INFINITY))
+                .map(|hit| hit.t)
+                .min_by(f32::total_cmp);
+            let found = bvh.hit(&ray, 0.001, f32::INFINITY).map(|hit| hit.t);
+            assert_eq!(found, expected, "{:?}", ray);
+        }
+    }
 
-fn box_y_compare(a: &Box<dyn Hittable>, b: &Box<dyn Hittable>) -> Ordering {
-    if let Some(box_left) = a.bounding_box() {
-        if let Some(box_right) = b.bounding_box() {
-            if let Some(cmp) = box_left.min.y.partial_cmp(&box_right.min.y) {
-                return cmp;
-            } else {
-                panic!("Can't compare");
-            }
+    #[test]
+    fn finds_the_nearest_hit_like_a_linear_search() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut hittables = spheres(&mut rng, 500);
+        hittables.push(Box::new(Plane::new(
+            Vec3::new(0.0, -5.0, 0.0),
+            Vec3::new(0.2, 1.0, 0.0),
+            Arc::new(Lambertian::new(Color::repeat(0.5))),
+        )));
+        let all: Vec<&dyn Hittable> = hittables.iter().map(|h| h.as_ref()).collect();
+
+        for max_leaf_size in [1, 4, 32] {
+            let config = BvhConfig {
+                max_leaf_size,
+                ..BvhConfig::default()
+            };
+            let bvh = Bvh::with_config(all.clone(), config).unwrap();
+            assert_eq!(bvh.stats.unbounded, 1);
+            assert_matches_brute_force(&bvh, &all, &mut rng);
         }
     }
 
-    panic!("No bounding box in BvhNode::new");
-}
+    #[test]
+    fn nested_bvhs_find_the_nearest_hit() {
+        let mut rng = SmallRng::seed_from_u64(2);
+        let inner = spheres(&mut rng, 200);
+        let outer = spheres(&mut rng, 50);
+        let blas = Bvh::new(inner.iter().collect()).unwrap();
+        let top = Bvh::new(
+            outer
+                .iter()
+                .map(|h| h.as_ref())
+                .chain([&blas as &dyn Hittable])
+                .collect(),
+        )
+        .unwrap();
 
-fn box_z_compare(a: &Box<dyn Hittable>, b: &Box<dyn Hittable>) -> Ordering {
-    if let Some(box_left) = a.bounding_box() {
-        if let Some(box_right) = b.bounding_box() {
-            if let Some(cmp) = box_left.min.z.partial_cmp(&box_right.min.z) {
-                return cmp;
-            } else {
-                panic!("Can't compare");
-            }
-        }
+        let all: Vec<&dyn Hittable> = inner.iter().chain(&outer).map(|h| h.as_ref()).collect();
+        assert_matches_brute_force(&top, &all, &mut rng);
     }
 
-    panic!("No bounding box in BvhNode::new");
+    #[test]
+    fn splits_hittables_with_equal_centroids() {
+        // Nested spheres, so every split plane has all of them on the same side.
+        let material = Arc::new(Lambertian::new(Color::repeat(0.5)));
+        let count = u16::MAX as usize + 1000;
+        let spheres: Vec<_> = (0..count)
+            .map(|i| Sphere::new(Vec3::zeros(), 1.0 + i as f32 * 1e-4, Arc::clone(&material)))
+            .collect();
+        let bvh = Bvh::new(spheres).unwrap();
+
+        let leaves = bvh.nodes.iter().filter(|node| node.count > 0);
+        assert_eq!(leaves.map(|node| node.count as usize).sum::<usize>(), count);
+        assert!(bvh.stats.depth <= MAX_DEPTH);
+        let hit = bvh
+            .hit(
+                &Ray::new(Vec3::new(0.0, 0.0, 20.0), Vec3::new(0.0, 0.0, -1.0)),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit.t - (20.0 - 1.0 - (count - 1) as f32 * 1e-4)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn leaves_at_the_maximum_depth_hold_their_hittables() {
+        // One far outlier makes the cheapest split peel off a single hittable, which would leave
+        // the rest in a leaf too large for its count at the maximum depth.
+        let count = u16::MAX as usize + 100;
+        let mut primitives: Vec<BuildPrimitive> = (0..count)
+            .map(|index| {
+                let x = if index == 0 {
+                    1000.0
+                } else {
+                    index as f32 * 1e-3
+                };
+                let aabb = Aabb {
+                    min: Vec3::new(x, 0.0, 0.0),
+                    max: Vec3::new(x + 1e-3, 1.0, 1.0),
+                };
+                BuildPrimitive {
+                    index,
+                    aabb,
+                    centroid: aabb.centroid(),
+                }
+            })
+            .collect();
+        let mut builder = Builder {
+            config: BvhConfig::default(),
+            nodes: Vec::new(),
+            order: Vec::new(),
+            depth: 0,
+        };
+        builder.build(&mut primitives, MAX_DEPTH - 1);
+
+        let leaves = builder.nodes.iter().filter(|node| node.count > 0);
+        assert_eq!(leaves.map(|node| node.count as usize).sum::<usize>(), count);
+        assert_eq!(builder.order.len(), count);
+    }
 }
@@ -0,0 +1,154 @@
+use crate::{
+    hittable::{
+        aabb::{Aabb, BOX_PADDING},
+        quad::Quad,
+        HitRecord, Hittable,
+    },
+    materials::Material,
+    ray::Ray,
+    vec::{Vec2, Vec3},
+};
+
+/// An axis aligned box between two opposite corners, made of six quads facing outwards.
+pub struct Cuboid<M: Material> {
+    sides: [Quad<M>; 6],
+    aabb: Aabb,
+}
+
+impl<M: Material + Clone> Cuboid<M> {
+    pub fn new(a: Vec3, b: Vec3, material: M) -> Self {
+        let min = a.inf(&b);
+        let max = a.sup(&b);
+        let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+        let side = |corner: Vec3, u: Vec3, v: Vec3| Quad::new(corner, u, v, material.clone());
+        Self {
+            sides: [
+                side(Vec3::new(min.x, min.y, max.z), dx, dy),  // front
+                side(Vec3::new(max.x, min.y, max.z), -dz, dy), // right
+                side(Vec3::new(max.x, min.y, min.z), -dx, dy), // back
+                side(Vec3::new(min.x, min.y, min.z), dz, dy),  // left
+                side(Vec3::new(min.x, max.y, max.z), dx, -dz), // top
+                side(Vec3::new(min.x, min.y, min.z), dx, dz),  // bottom
+            ],
+            aabb: Aabb { min, max }.padded(BOX_PADDING),
+        }
+    }
+}
+
+impl<M: Material + Sync> Hittable for Cuboid<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, mut t_max: f32) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        for side in &self.sides {
+            if let Some(hit) = side.hit(ray, t_min, t_max) {
+                t_max = hit.t;
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.aabb)
+    }
+
+    // Light samples pick one of the sides uniformly.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+        let sum: f32 = self
+            .sides
+            .iter()
+            .map(|side| side.pdf_value(origin, direction, time))
+            .sum();
+        sum / self.sides.len() as f32
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        self.sides[0].material()
+    }
+
+    fn for_each_material(&self, f: &mut dyn FnMut(&dyn Material)) {
+        self.sides[0].for_each_material(f)
+    }
+
+    fn random_direction(&self, origin: &Vec3, time: f32, u: Vec2) -> Vec3 {
+        let scaled = u.x * self.sides.len() as f32;
+        let side = (scaled as usize).min(self.sides.len() - 1);
+        let u = Vec2::new(scaled - side as f32, u.y);
+        self.sides[side].random_direction(origin, time, u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::lambertian::Lambertian, vec::Color};
+    use std::sync::Arc;
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(Color::repeat(0.5)))
+    }
+
+    // Corners given in any order.
+    fn cuboid() -> Cuboid<Arc<dyn Material>> {
+        Cuboid::new(
+            Vec3::new(1.0, 2.0, -3.0),
+            Vec3::new(-1.0, 0.0, 1.0),
+            material(),
+        )
+    }
+
+    #[test]
+    fn every_side_faces_outwards() {
+        let cuboid = cuboid();
+        let center = Vec3::new(0.0, 1.0, -1.0);
+        for axis in [Vec3::x(), Vec3::y(), Vec3::z()] {
+            for direction in [axis, -axis] {
+                // From outside towards the centre, hitting the side facing the ray.
+                let ray = Ray::new(center + 10.0 * direction, -direction);
+                let hit = cuboid.hit(&ray, 0.001, f32::INFINITY).unwrap();
+                assert!((hit.normal - direction).norm() < 1e-6, "{:?}", direction);
+                let reach = (hit.point - center).dot(&direction);
+                let half_size = Vec3::new(1.0, 1.0, 2.0).dot(&axis);
+                assert!((reach - half_size).abs() < 1e-5, "{:?}", direction);
+                assert!((hit.t - (10.0 - half_size)).abs() < 1e-5, "{:?}", direction);
+            }
+        }
+    }
+
+    #[test]
+    fn hits_the_far_side_from_inside() {
+        let cuboid = cuboid();
+        let ray = Ray::new(Vec3::new(0.5, 1.0, -1.0), Vec3::new(0.0, 0.0, 2.0));
+        let hit = cuboid.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-6);
+        assert!((hit.point - Vec3::new(0.5, 1.0, 1.0)).norm() < 1e-6);
+        assert_eq!(hit.normal, Vec3::z());
+    }
+
+    #[test]
+    fn misses_beside_it() {
+        let cuboid = cuboid();
+        let ray = Ray::new(Vec3::new(1.1, 1.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(cuboid.hit(&ray, 0.001, f32::INFINITY).is_none());
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(cuboid.hit(&ray, 0.001, 3.5).is_none());
+    }
+
+    #[test]
+    fn bounds_are_the_corners() {
+        let bounds = cuboid().bounding_box().unwrap();
+        assert_eq!(bounds.min, Vec3::new(-1.0, 0.0, -3.0));
+        assert_eq!(bounds.max, Vec3::new(1.0, 2.0, 1.0));
+
+        // A box without thickness is padded like a quad.
+        let flat = Cuboid::new(
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            material(),
+        );
+        let bounds = flat.bounding_box().unwrap();
+        assert!(bounds.min.y < 1.0 && bounds.max.y > 1.0);
+    }
+}
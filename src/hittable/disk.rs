@@ -0,0 +1,191 @@
+use std::f32::consts::PI;
+
+use crate::{
+    hittable::{
+        aabb::{Aabb, BOX_PADDING},
+        HitRecord, Hittable,
+    },
+    materials::Material,
+    ray::Ray,
+    sampler::warp::concentric_disk,
+    vec::{orthonormal_basis, Vec2, Vec3},
+};
+
+const EPSILON: f32 = 1e-8;
+
+/// A disk facing along `normal`.
+///
+/// Texture coordinates are polar: `u` is the angle around the normal over a full turn and `v` the
+/// distance from the center over the radius.
+pub struct Disk<M: Material> {
+    center: Vec3,
+    normal: Vec3,
+    radius: f32,
+    // In the plane of the disk, where the angle starts.
+    tangent: Vec3,
+    bitangent: Vec3,
+    material: M,
+}
+
+impl<M: Material> Disk<M> {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: M) -> Self {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = orthonormal_basis(&normal);
+        Self {
+            center,
+            normal,
+            radius,
+            tangent,
+            bitangent,
+            material,
+        }
+    }
+}
+
+impl<M: Material + Sync> Hittable for Disk<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(&(self.center - ray.origin())) / denominator;
+        if t <= t_min || t_max <= t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let p = point - self.center;
+        let distance_squared = p.magnitude_squared();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+
+        let phi = p.dot(&self.bitangent).atan2(p.dot(&self.tangent));
+        Some(HitRecord {
+            point,
+            normal: self.normal,
+            t,
+            u: (phi + PI) / (2.0 * PI),
+            v: distance_squared.sqrt() / self.radius,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // How far the rim reaches along each axis.
+        let extent = self
+            .normal
+            .map(|n| self.radius * (1.0 - n * n).max(0.0).sqrt());
+        Some(
+            Aabb {
+                min: self.center - extent,
+                max: self.center + extent,
+            }
+            .padded(BOX_PADDING),
+        )
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+        let ray = Ray::new(*origin, *direction).with_time(time);
+        let Some(hit) = self.hit(&ray, 0.001, f32::INFINITY) else {
+            return 0.0;
+        };
+
+        let area = PI * self.radius * self.radius;
+        let distance_squared = hit.t * hit.t * direction.magnitude_squared();
+        let cosine = self.normal.dot(direction).abs() / direction.magnitude();
+
+        distance_squared / (cosine * area)
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn random_direction(&self, origin: &Vec3, _time: f32, u: Vec2) -> Vec3 {
+        // Uniform over the area.
+        let d = self.radius * concentric_disk(u);
+        self.center + d.x * self.tangent + d.y * self.bitangent - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hittable::assert_light_sampling, materials::lambertian::Lambertian, vec::Color};
+
+    // Radius two around (1, 2, 3), facing up.
+    fn disk() -> Disk<Lambertian> {
+        Disk::new(
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(0.0, 5.0, 0.0),
+            2.0,
+            Lambertian::new(Color::repeat(0.5)),
+        )
+    }
+
+    fn hit_at(disk: &Disk<Lambertian>, offset: Vec3) -> Option<HitRecord<'_>> {
+        let ray = Ray::new(disk.center + offset + Vec3::y(), Vec3::new(0.0, -1.0, 0.0));
+        disk.hit(&ray, 0.001, f32::INFINITY)
+    }
+
+    #[test]
+    fn hits_within_the_radius() {
+        let disk = disk();
+        let hit = hit_at(&disk, Vec3::zeros()).unwrap();
+        assert!((hit.t - 1.0).abs() < 1e-6);
+        assert_eq!(hit.normal, Vec3::y());
+        assert!(hit_at(&disk, Vec3::new(1.9, 0.0, 0.0)).is_some());
+        assert!(hit_at(&disk, Vec3::new(1.4, 0.0, 1.4)).is_some());
+        assert!(hit_at(&disk, Vec3::new(2.1, 0.0, 0.0)).is_none());
+        assert!(hit_at(&disk, Vec3::new(1.5, 0.0, 1.5)).is_none());
+        // In the plane of the disk.
+        let ray = Ray::new(Vec3::new(-5.0, 2.0, 3.0), Vec3::x());
+        assert!(disk.hit(&ray, 0.001, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn coordinates_are_polar() {
+        let disk = disk();
+        let uv = |offset: Vec3| {
+            let hit = hit_at(&disk, offset).unwrap();
+            (hit.u, hit.v)
+        };
+        // Along the tangent the angle is zero, halfway through the range of `u`.
+        let (u, v) = uv(disk.tangent);
+        assert!((u - 0.5).abs() < 1e-6 && (v - 0.5).abs() < 1e-6);
+        // A quarter turn on, towards the bitangent.
+        let (u, v) = uv(1.9 * disk.bitangent);
+        assert!((u - 0.75).abs() < 1e-6 && (v - 0.95).abs() < 1e-6);
+        let (u, _) = uv(-disk.bitangent);
+        assert!((u - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn flat_bounds_are_padded() {
+        let bounds = disk().bounding_box().unwrap();
+        assert_eq!((bounds.min.x, bounds.max.x), (-1.0, 3.0));
+        assert_eq!((bounds.min.z, bounds.max.z), (1.0, 5.0));
+        assert!(bounds.min.y < 2.0 && bounds.max.y > 2.0);
+        assert!(bounds.max.y - bounds.min.y <= 1e-2);
+
+        // Tilted, the rim reaches less far along the axes it leans towards.
+        let tilted = Disk::new(
+            Vec3::zeros(),
+            Vec3::new(1.0, 1.0, 0.0),
+            1.0,
+            Lambertian::new(Color::repeat(0.5)),
+        );
+        let bounds = tilted.bounding_box().unwrap();
+        let half = 0.5f32.sqrt();
+        assert!((bounds.max - Vec3::new(half, half, 1.0)).norm() < 1e-5);
+        assert!((bounds.min + Vec3::new(half, half, 1.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn samples_directions_with_their_pdf() {
+        assert_light_sampling(&disk(), Vec3::new(1.5, 3.0, 2.5), 0.0);
+        assert_light_sampling(&disk(), Vec3::new(0.0, 1.0, 3.0), 0.0);
+    }
+}
@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::{aabb::Aabb, HitRecord, Hittable},
+    materials::Material,
+    ray::Ray,
+    transform::Transform,
+    vec::{Vec2, Vec3},
+};
+
+/// A shared hittable placed in the world by a [`Transform`].
+///
+/// Copies of one object only cost the transform each. Rays are taken into the object's space
+/// without normalizing their direction, so hit distances are the same in both spaces. Lights
+/// should only be moved, rotated and scaled uniformly, their sampling densities ignore stretching.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    aabb: Option<Aabb>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        let aabb = object.bounding_box().map(|aabb| transform.aabb(&aabb));
+        Self {
+            object,
+            transform,
+            aabb,
+        }
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    #[inline(always)]
+    fn to_object(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.transform.inverse_point(&ray.origin()),
+            self.transform.inverse_vector(&ray.direction()),
+        )
+        .with_time(ray.time())
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut hit = self.object.hit(&self.to_object(ray), t_min, t_max)?;
+        hit.point = ray.at(hit.t);
+        hit.normal = self.transform.normal(&hit.normal).normalize();
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.aabb
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+        self.object.pdf_value(
+            &self.transform.inverse_point(origin),
+            &self.transform.inverse_vector(direction),
+            time,
+        )
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        self.object.material()
+    }
+
+    fn for_each_material(&self, f: &mut dyn FnMut(&dyn Material)) {
+        self.object.for_each_material(f)
+    }
+
+    fn random_direction(&self, origin: &Vec3, time: f32, u: Vec2) -> Vec3 {
+        let direction =
+            self.object
+                .random_direction(&self.transform.inverse_point(origin), time, u);
+        self.transform.vector(&direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::{assert_light_sampling, sphere::Sphere},
+        materials::lambertian::Lambertian,
+        vec::Color,
+    };
+
+    fn unit_sphere() -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(
+            Vec3::zeros(),
+            1.0,
+            Lambertian::new(Color::repeat(0.5)),
+        ))
+    }
+
+    fn instance(transforms: &[Transform]) -> Instance {
+        let transform = transforms
+            .iter()
+            .fold(Transform::identity(), |all, next| all.then(next));
+        Instance::new(unit_sphere(), transform)
+    }
+
+    fn assert_close(a: &Vec3, b: &Vec3) {
+        assert!((a - b).amax() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn hits_in_world_space() {
+        let instance = instance(&[
+            Transform::rotation(Vec3::new(1.0, 1.0, 0.0), 70.0).unwrap(),
+            Transform::translation(Vec3::new(3.0, -1.0, 2.0)).unwrap(),
+        ]);
+        // Not normalized, so `t` is half the distance travelled.
+        let ray = Ray::new(Vec3::new(3.0, -1.0, 7.0), Vec3::new(0.0, 0.0, -2.0));
+        let hit = instance.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-5);
+        assert_close(&hit.point, &Vec3::new(3.0, -1.0, 3.0));
+        assert_close(&hit.normal, &Vec3::z());
+        assert!(instance.hit(&ray, 0.001, 1.5).is_none());
+
+        let beside = Ray::new(Vec3::new(4.1, -1.0, 7.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(instance.hit(&beside, 0.001, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn stretched_normals_stay_perpendicular() {
+        // The ellipsoid x² / 4 + y² + z² = 1.
+        let instance = instance(&[Transform::scaling(Vec3::new(2.0, 1.0, 1.0)).unwrap()]);
+        let x = 2.0f32.sqrt();
+        let ray = Ray::new(Vec3::new(x, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let hit = instance.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert_close(&hit.point, &Vec3::new(x, 0.5f32.sqrt(), 0.0));
+        // Along the gradient (x / 4, y, z), not the object's normal stretched like a point.
+        assert_close(&hit.normal, &(Vec3::new(1.0, 2.0, 0.0) / 5.0f32.sqrt()));
+    }
+
+    #[test]
+    fn bounds_hold_the_transformed_corners() {
+        let transform = Transform::scaling(Vec3::new(2.0, 1.0, 0.5))
+            .unwrap()
+            .then(&Transform::rotation(Vec3::new(0.0, 0.0, 1.0), 45.0).unwrap())
+            .then(&Transform::translation(Vec3::new(1.0, 2.0, 3.0)).unwrap());
+        let instance = Instance::new(unit_sphere(), transform);
+        let bounds = instance.bounding_box().unwrap();
+        let object = unit_sphere().bounding_box().unwrap();
+        for corner in 0..8 {
+            let pick = |a: usize| {
+                if corner & (1 << a) == 0 {
+                    object.min[a]
+                } else {
+                    object.max[a]
+                }
+            };
+            let point = transform.point(&Vec3::new(pick(0), pick(1), pick(2)));
+            for a in 0..3 {
+                assert!(bounds.min[a] <= point[a] + 1e-5 && point[a] <= bounds.max[a] + 1e-5);
+            }
+        }
+        // And no more than them: half the diagonal of the 4 by 2 rectangle in the xy plane.
+        let reach = 4.5f32.sqrt();
+        assert!(bounds.max.x - 1.0 < reach + 1e-3 && bounds.max.z - 3.0 < 0.5 + 1e-3);
+    }
+
+    #[test]
+    fn samples_moved_and_scaled_lights() {
+        let instance = instance(&[
+            Transform::scaling(Vec3::repeat(0.5)).unwrap(),
+            Transform::translation(Vec3::new(1.0, 2.0, 3.0)).unwrap(),
+        ]);
+        assert_light_sampling(&instance, Vec3::new(1.0, 2.75, 3.0), 0.0);
+        assert_light_sampling(&instance, Vec3::new(1.5, 1.5, 3.2), 0.0);
+    }
+}
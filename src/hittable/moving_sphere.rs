@@ -0,0 +1,138 @@
+use crate::{
+    hittable::{
+        aabb::{surrounding_box, Aabb},
+        sphere::{hit_sphere, sphere_box, sphere_direction, sphere_pdf},
+        HitRecord, Hittable,
+    },
+    materials::Material,
+    ray::Ray,
+    vec::{Vec2, Vec3},
+};
+
+/// A sphere moving in a straight line from `center0` at `time0` to `center1` at `time1`.
+///
+/// It stays put before `time0` and after `time1`, so a shutter interval that is longer than the
+/// movement doesn't carry it outside its bounding box.
+pub struct MovingSphere<M: Material> {
+    center0: Vec3,
+    center1: Vec3,
+    time0: f32,
+    time1: f32,
+    radius: f32,
+    material: M,
+}
+
+impl<M: Material> MovingSphere<M> {
+    pub fn new(
+        (center0, time0): (Vec3, f32),
+        (center1, time1): (Vec3, f32),
+        radius: f32,
+        material: M,
+    ) -> Self {
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material,
+        }
+    }
+
+    #[inline(always)]
+    pub fn center(&self, time: f32) -> Vec3 {
+        if self.time1 <= self.time0 {
+            return self.center0;
+        }
+        let t = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        self.center0 + t * (self.center1 - self.center0)
+    }
+}
+
+impl<M: Material + Sync> Hittable for MovingSphere<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let center = self.center(ray.time());
+        hit_sphere(&center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(surrounding_box(
+            &sphere_box(&self.center0, self.radius),
+            &sphere_box(&self.center1, self.radius),
+        ))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+        let ray = Ray::new(*origin, *direction).with_time(time);
+        if self.hit(&ray, 0.001, f32::INFINITY).is_none() {
+            return 0.0;
+        }
+
+        sphere_pdf(&self.center(time), self.radius, origin)
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn random_direction(&self, origin: &Vec3, time: f32, u: Vec2) -> Vec3 {
+        sphere_direction(&self.center(time), self.radius, origin, u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hittable::assert_light_sampling, materials::lambertian::Lambertian, vec::Color};
+
+    // From the origin at time 0 to four units along x at time 1.
+    fn sphere() -> MovingSphere<Lambertian> {
+        MovingSphere::new(
+            (Vec3::zeros(), 0.0),
+            (Vec3::new(4.0, 0.0, 0.0), 1.0),
+            1.0,
+            Lambertian::new(Color::repeat(0.5)),
+        )
+    }
+
+    // Where a ray straight down the z axis through `x` hits the sphere at `time`.
+    fn hit_at(x: f32, time: f32) -> Option<Vec3> {
+        let ray = Ray::new(Vec3::new(x, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0)).with_time(time);
+        sphere()
+            .hit(&ray, 0.001, f32::INFINITY)
+            .map(|hit| hit.point)
+    }
+
+    #[test]
+    fn moves_with_the_time_of_the_ray() {
+        assert_eq!(hit_at(0.0, 0.0), Some(Vec3::new(0.0, 0.0, 1.0)));
+        assert_eq!(hit_at(4.0, 0.0), None);
+        assert_eq!(hit_at(4.0, 1.0), Some(Vec3::new(4.0, 0.0, 1.0)));
+        assert_eq!(hit_at(0.0, 1.0), None);
+        assert_eq!(hit_at(2.0, 0.5), Some(Vec3::new(2.0, 0.0, 1.0)));
+        // Resting outside the time range.
+        assert_eq!(hit_at(0.0, -1.0), Some(Vec3::new(0.0, 0.0, 1.0)));
+        assert_eq!(hit_at(4.0, 2.0), Some(Vec3::new(4.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn bounds_hold_both_ends() {
+        let bounds = sphere().bounding_box().unwrap();
+        assert!(bounds
+            .min
+            .iter()
+            .zip(&[-1.0, -1.0, -1.0])
+            .all(|(a, b)| a <= b));
+        assert!(bounds.max.iter().zip(&[5.0, 1.0, 1.0]).all(|(a, b)| a >= b));
+    }
+
+    #[test]
+    fn samples_directions_with_their_pdf() {
+        // Close to wherever the sphere is at the time.
+        let sphere = sphere();
+        for time in [0.0, 0.5, 1.0] {
+            let origin = sphere.center(time) + Vec3::new(-0.5, -1.0, 1.0);
+            assert_light_sampling(&sphere, origin, time);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    hittable::{aabb::Aabb, HitRecord, Hittable},
+    materials::Material,
+    ray::Ray,
+    vec::{orthonormal_basis, Vec3},
+};
+
+const EPSILON: f32 = 1e-8;
+
+/// An infinite plane through `point` facing along `normal`.
+///
+/// It has no bounding box. Texture coordinates repeat every unit along two directions in the
+/// plane.
+pub struct Plane<M: Material> {
+    point: Vec3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    material: M,
+}
+
+impl<M: Material> Plane<M> {
+    pub fn new(point: Vec3, normal: Vec3, material: M) -> Self {
+        let normal = normal.normalize();
+        let (tangent, bitangent) = orthonormal_basis(&normal);
+        Self {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            material,
+        }
+    }
+}
+
+impl<M: Material + Sync> Hittable for Plane<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(&(self.point - ray.origin())) / denominator;
+        if t <= t_min || t_max <= t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let p = point - self.point;
+        Some(HitRecord {
+            point,
+            normal: self.normal,
+            t,
+            u: p.dot(&self.tangent).rem_euclid(1.0),
+            v: p.dot(&self.bitangent).rem_euclid(1.0),
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::lambertian::Lambertian, vec::Color};
+
+    // The floor one unit down.
+    fn plane() -> Plane<Lambertian> {
+        Plane::new(
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Lambertian::new(Color::repeat(0.5)),
+        )
+    }
+
+    #[test]
+    fn hits_from_both_sides() {
+        let plane = plane();
+        let from_above = Ray::new(Vec3::new(3.0, 1.0, -7.0), Vec3::new(0.0, -1.0, 0.0));
+        let hit = plane.hit(&from_above, 0.001, f32::INFINITY).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-6);
+        assert!((hit.point - Vec3::new(3.0, -1.0, -7.0)).norm() < 1e-6);
+        assert_eq!(hit.normal, Vec3::y());
+
+        // Far away and at a grazing angle.
+        let from_below = Ray::new(Vec3::new(0.0, -2.0, 0.0), Vec3::new(1000.0, 1.0, 0.0));
+        let hit = plane.hit(&from_below, 0.001, f32::INFINITY).unwrap();
+        assert!((hit.point - Vec3::new(1000.0, -1.0, 0.0)).norm() < 1e-2);
+        assert_eq!(hit.normal, Vec3::y());
+
+        let parallel = Ray::new(Vec3::zeros(), Vec3::x());
+        assert!(plane.hit(&parallel, 0.001, f32::INFINITY).is_none());
+        let away = Ray::new(Vec3::zeros(), Vec3::y());
+        assert!(plane.hit(&away, 0.001, f32::INFINITY).is_none());
+        assert!(plane.hit(&from_above, 0.001, 1.5).is_none());
+    }
+
+    #[test]
+    fn coordinates_repeat_every_unit() {
+        let plane = plane();
+        let uv = |x: f32, z: f32| {
+            let ray = Ray::new(Vec3::new(x, 1.0, z), Vec3::new(0.0, -1.0, 0.0));
+            let hit = plane.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            (hit.u, hit.v)
+        };
+        for (x, z) in [(0.3, 0.7), (-12.6, 40.2), (1e3, -1e3)] {
+            let (u, v) = uv(x, z);
+            assert!(
+                (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v),
+                "{} {}",
+                u,
+                v
+            );
+        }
+        // A whole unit along both directions in the plane comes back to the same coordinates.
+        let step = plane.tangent + plane.bitangent;
+        let (u0, v0) = uv(0.25, 0.25);
+        let (u1, v1) = uv(0.25 + step.x, 0.25 + step.z);
+        assert!((u0 - u1).abs() < 1e-5 && (v0 - v1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn is_unbounded() {
+        assert!(plane().bounding_box().is_none());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    hittable::{
+        aabb::{surrounding_box, Aabb, BOX_PADDING},
+        HitRecord, Hittable,
+    },
+    materials::Material,
+    ray::Ray,
+    vec::{Vec2, Vec3},
+};
+
+const EPSILON: f32 = 1e-8;
+
+/// A parallelogram spanned by the edges `u` and `v` from `corner`.
+///
+/// Texture coordinates run from 0 to 1 along the edges, the normal is `u × v`.
+pub struct Quad<M: Material> {
+    corner: Vec3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    // Maps a point of the plane relative to `corner` to its coordinates along `u` and `v`.
+    w: Vec3,
+    area: f32,
+    material: M,
+}
+
+impl<M: Material> Quad<M> {
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, material: M) -> Self {
+        let n = u.cross(&v);
+        Self {
+            corner,
+            u,
+            v,
+            normal: n.normalize(),
+            w: n / n.dot(&n),
+            area: n.magnitude(),
+            material,
+        }
+    }
+}
+
+impl<M: Material + Sync> Hittable for Quad<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(&ray.direction());
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(&(self.corner - ray.origin())) / denominator;
+        if t <= t_min || t_max <= t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let p = point - self.corner;
+        let alpha = self.w.dot(&p.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&p));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some(HitRecord {
+            point,
+            normal: self.normal,
+            t,
+            u: alpha,
+            v: beta,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let diagonal = |a: Vec3, b: Vec3| Aabb {
+            min: a.inf(&b),
+            max: a.sup(&b),
+        };
+        Some(
+            surrounding_box(
+                &diagonal(self.corner, self.corner + self.u + self.v),
+                &diagonal(self.corner + self.u, self.corner + self.v),
+            )
+            .padded(BOX_PADDING),
+        )
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+        let ray = Ray::new(*origin, *direction).with_time(time);
+        let Some(hit) = self.hit(&ray, 0.001, f32::INFINITY) else {
+            return 0.0;
+        };
+
+        let distance_squared = hit.t * hit.t * direction.magnitude_squared();
+        let cosine = self.normal.dot(direction).abs() / direction.magnitude();
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn random_direction(&self, origin: &Vec3, _time: f32, u: Vec2) -> Vec3 {
+        // Uniform over the area.
+        self.corner + u.x * self.u + u.y * self.v - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::assert_light_sampling,
+        materials::{lambertian::Lambertian, metal::Metal},
+        vec::Color,
+    };
+
+    // Two by three in the xy plane, facing +z.
+    fn quad() -> Quad<Lambertian> {
+        Quad::new(
+            Vec3::zeros(),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 3.0, 0.0),
+            Lambertian::new(Color::repeat(0.5)),
+        )
+    }
+
+    fn hit_at(x: f32, y: f32) -> Option<(f32, f32)> {
+        let ray = Ray::new(Vec3::new(x, y, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        quad()
+            .hit(&ray, 0.001, f32::INFINITY)
+            .map(|hit| (hit.u, hit.v))
+    }
+
+    #[test]
+    fn coordinates_run_along_the_edges() {
+        assert_eq!(hit_at(1.0, 1.5), Some((0.5, 0.5)));
+        assert_eq!(hit_at(0.5, 0.75), Some((0.25, 0.25)));
+        assert_eq!(hit_at(2.0, 3.0), Some((1.0, 1.0)));
+        assert_eq!(hit_at(2.1, 1.0), None);
+        assert_eq!(hit_at(1.0, -0.1), None);
+    }
+
+    #[test]
+    fn flat_bounds_are_padded() {
+        let bounds = quad().bounding_box().unwrap();
+        assert_eq!((bounds.min.x, bounds.max.x), (0.0, 2.0));
+        assert_eq!((bounds.min.y, bounds.max.y), (0.0, 3.0));
+        assert!(bounds.min.z < 0.0 && bounds.max.z > 0.0);
+        assert!(bounds.max.z - bounds.min.z <= 1e-3);
+    }
+
+    #[test]
+    fn samples_directions_with_their_pdf() {
+        assert_light_sampling(&quad(), Vec3::new(0.5, 1.0, 1.0), 0.0);
+        // From behind, lights shine both ways.
+        assert_light_sampling(&quad(), Vec3::new(1.5, 0.5, -0.5), 0.0);
+    }
+
+    #[test]
+    fn metal_reflects_off_the_back() {
+        let quad = Quad::new(
+            Vec3::zeros(),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Metal::new(Color::repeat(0.8), 0.0),
+        );
+        // The normal is `u × v`, up along z, and the ray comes from below.
+        let ray = Ray::new(Vec3::new(1.0, -2.0, -3.0), Vec3::new(0.0, 1.0, 1.0));
+        let hit = quad.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((hit.normal - Vec3::z()).norm() < 1e-6);
+        let sample = hit
+            .material
+            .sample(&ray, &hit, 0.5, Vec2::new(0.5, 0.5))
+            .unwrap();
+        let direction = sample.ray.direction().normalize();
+        assert!((direction - Vec3::new(0.0, 1.0, -1.0).normalize()).norm() < 1e-6);
+        assert_eq!(sample.attenuation, Color::repeat(0.8));
+    }
+}
@@ -1,8 +1,10 @@
+use std::f32::consts::PI;
+
 use crate::{
     hittable::{aabb::Aabb, HitRecord, Hittable},
     materials::Material,
     ray::Ray,
-    vec::Vec3,
+    vec::{orthonormal_basis, Vec2, Vec3},
 };
 
 pub struct Sphere<M: Material> {
@@ -11,6 +13,13 @@ pub struct Sphere<M: Material> {
     material: M,
 }
 
+fn sphere_uv(normal: &Vec3) -> (f32, f32) {
+    let theta = (-normal.y).acos();
+    let phi = (-normal.z).atan2(normal.x) + PI;
+
+    (phi / (2.0 * PI), theta / PI)
+}
+
 impl<M: Material> Sphere<M> {
     pub fn new(center: Vec3, radius: f32, material: M) -> Self {
         Self {
@@ -22,48 +31,129 @@ impl<M: Material> Sphere<M> {
 }
 
 impl<M: Material + Sync> Hittable for Sphere<M> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().dot(&ray.direction());
-        let b = oc.dot(&ray.direction());
-        let c = oc.dot(&oc) - self.radius * self.radius;
-
-        let discriminant = b * b - a * c;
-
-        if discriminant > 0.0 {
-            let sqrt_discriminant = discriminant.sqrt();
-            let t = (-b - sqrt_discriminant) / a;
-
-            if t_min < t && t < t_max {
-                let point = ray.at(t);
-                let normal = (point - self.center) / self.radius;
-                return Some(HitRecord {
-                    point,
-                    normal,
-                    t,
-                    material: &self.material,
-                });
-            }
-            let t = (-b + sqrt_discriminant) / a;
-            if t_min < t && t < t_max {
-                let point = ray.at(t);
-                let normal = (point - self.center) / self.radius;
-                return Some(HitRecord {
-                    point,
-                    normal,
-                    t,
-                    material: &self.material,
-                });
-            }
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_sphere(&self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(sphere_box(&self.center, self.radius))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+        let ray = Ray::new(*origin, *direction).with_time(time);
+        if self.hit(&ray, 0.001, f32::INFINITY).is_none() {
+            return 0.0;
         }
 
-        None
+        sphere_pdf(&self.center, self.radius, origin)
     }
 
-    fn bounding_box(&self) -> Option<Aabb> {
-        Some(Aabb {
-            min: self.center - Vec3::new(self.radius, self.radius, self.radius),
-            max: self.center + Vec3::new(self.radius, self.radius, self.radius),
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn random_direction(&self, origin: &Vec3, _time: f32, u: Vec2) -> Vec3 {
+        sphere_direction(&self.center, self.radius, origin, u)
+    }
+}
+
+// Shared with `MovingSphere`, which places the sphere at the ray's time first.
+pub(super) fn hit_sphere<'a>(
+    center: &Vec3,
+    radius: f32,
+    material: &'a dyn Material,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<HitRecord<'a>> {
+    let oc = ray.origin() - center;
+    let a = ray.direction().dot(&ray.direction());
+    let b = oc.dot(&ray.direction());
+    let c = oc.dot(&oc) - radius * radius;
+
+    let discriminant = b * b - a * c;
+    if discriminant <= 0.0 {
+        return None;
+    }
+
+    let sqrt_discriminant = discriminant.sqrt();
+    [(-b - sqrt_discriminant) / a, (-b + sqrt_discriminant) / a]
+        .into_iter()
+        .find(|&t| t_min < t && t < t_max)
+        .map(|t| {
+            let point = ray.at(t);
+            let normal = (point - center) / radius;
+            let (u, v) = sphere_uv(&normal);
+            HitRecord {
+                point,
+                normal,
+                t,
+                u,
+                v,
+                material,
+            }
         })
+}
+
+pub(super) fn sphere_box(center: &Vec3, radius: f32) -> Aabb {
+    Aabb {
+        min: center - Vec3::new(radius, radius, radius),
+        max: center + Vec3::new(radius, radius, radius),
+    }
+}
+
+// Density of `sphere_direction` from `origin`, for a direction known to hit the sphere.
+pub(super) fn sphere_pdf(center: &Vec3, radius: f32, origin: &Vec3) -> f32 {
+    match cos_theta_max(radius, &(center - origin)) {
+        Some(cos_theta_max) => 1.0 / (2.0 * PI * (1.0 - cos_theta_max)),
+        None => 1.0 / (4.0 * PI),
+    }
+}
+
+pub(super) fn sphere_direction(center: &Vec3, radius: f32, origin: &Vec3, u: Vec2) -> Vec3 {
+    let to_center = center - origin;
+    let (r1, r2) = (u.x, u.y);
+    let phi = 2.0 * PI * r1;
+
+    // Inside the sphere every direction hits it, so fall back to the whole sphere of directions.
+    let Some(cos_theta_max) = cos_theta_max(radius, &to_center) else {
+        let z = 1.0 - 2.0 * r2;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        return Vec3::new(r * phi.cos(), r * phi.sin(), z);
+    };
+
+    // Uniform in the cone of directions subtended by the sphere.
+    let z = 1.0 + r2 * (cos_theta_max - 1.0);
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let w = to_center.normalize();
+    let (u, v) = orthonormal_basis(&w);
+
+    r * phi.cos() * u + r * phi.sin() * v + z * w
+}
+
+#[inline(always)]
+fn cos_theta_max(radius: f32, to_center: &Vec3) -> Option<f32> {
+    let distance_squared = to_center.magnitude_squared();
+    let radius_squared = radius * radius;
+    if distance_squared <= radius_squared {
+        return None;
+    }
+
+    Some((1.0 - radius_squared / distance_squared).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hittable::assert_light_sampling, materials::lambertian::Lambertian, vec::Color};
+
+    #[test]
+    fn samples_directions_with_their_pdf() {
+        let sphere = Sphere::new(
+            Vec3::new(0.5, 1.0, -1.0),
+            1.0,
+            Lambertian::new(Color::repeat(0.5)),
+        );
+        assert_light_sampling(&sphere, Vec3::zeros(), 0.0);
     }
 }
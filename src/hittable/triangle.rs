@@ -0,0 +1,449 @@
+use anyhow::{anyhow, Result};
+use nalgebra::Vector2;
+use std::sync::Arc;
+
+use crate::{
+    hittable::{
+        aabb::{Aabb, BOX_PADDING},
+        bvh::Bvh,
+        HitRecord, Hittable,
+    },
+    materials::Material,
+    ray::Ray,
+    transform::Transform,
+    vec::{Vec2, Vec3},
+};
+
+const EPSILON: f32 = 1e-8;
+
+pub struct TriangleMesh {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    texcoords: Vec<Vector2<f32>>,
+    indices: Vec<[u32; 3]>,
+    material: Arc<dyn Material>,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Vec3>, indices: Vec<[u32; 3]>, material: Arc<dyn Material>) -> Self {
+        assert!(
+            indices
+                .iter()
+                .flatten()
+                .all(|&i| (i as usize) < positions.len()),
+            "Triangle index out of range"
+        );
+
+        Self {
+            positions,
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            indices,
+            material,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Self {
+        assert_eq!(normals.len(), self.positions.len(), "One normal per vertex");
+        self.normals = normals;
+        self
+    }
+
+    pub fn with_texcoords(mut self, texcoords: Vec<Vector2<f32>>) -> Self {
+        assert_eq!(
+            texcoords.len(),
+            self.positions.len(),
+            "One texture coordinate per vertex"
+        );
+        self.texcoords = texcoords;
+        self
+    }
+
+    pub fn material(&self) -> &dyn Material {
+        self.material.as_ref()
+    }
+
+    /// A copy with `transform` applied to its vertices and normals.
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Self {
+            positions: self.positions.iter().map(|p| transform.point(p)).collect(),
+            normals: self
+                .normals
+                .iter()
+                .map(|n| transform.normal(n).normalize())
+                .collect(),
+            texcoords: self.texcoords.clone(),
+            indices: self.indices.clone(),
+            material: Arc::clone(&self.material),
+        }
+    }
+
+    fn area(&self, index: usize) -> f32 {
+        let [a, b, c] = self.indices[index].map(|i| self.positions[i as usize]);
+        0.5 * (b - a).cross(&(c - a)).magnitude()
+    }
+
+    // A point of triangle `index` picked uniformly over its area with the uniform sample `u`.
+    fn sample_point(&self, index: usize, u: Vec2) -> Vec3 {
+        let [a, b, c] = self.indices[index].map(|i| self.positions[i as usize]);
+        let r1 = u.x.sqrt();
+        let r2 = u.y;
+        (1.0 - r1) * a + r1 * (1.0 - r2) * b + r1 * r2 * c
+    }
+
+    pub fn triangles(self: &Arc<Self>) -> impl Iterator<Item = Triangle> + '_ {
+        (0..self.indices.len()).map(|index| Triangle {
+            mesh: Arc::clone(self),
+            index,
+        })
+    }
+}
+
+pub struct Triangle {
+    mesh: Arc<TriangleMesh>,
+    index: usize,
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, material: Arc<dyn Material>) -> Self {
+        Self {
+            mesh: Arc::new(TriangleMesh::new(vec![a, b, c], vec![[0, 1, 2]], material)),
+            index: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn vertices(&self) -> [usize; 3] {
+        let [a, b, c] = self.mesh.indices[self.index];
+        [a as usize, b as usize, c as usize]
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // Möller–Trumbore
+        let [i0, i1, i2] = self.vertices();
+        let p0 = self.mesh.positions[i0];
+        let edge1 = self.mesh.positions[i1] - p0;
+        let edge2 = self.mesh.positions[i2] - p0;
+
+        let p_vec = ray.direction().cross(&edge2);
+        let det = edge1.dot(&p_vec);
+        if det.abs() < EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let t_vec = ray.origin() - p0;
+        let b1 = t_vec.dot(&p_vec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let q_vec = t_vec.cross(&edge1);
+        let b2 = ray.direction().dot(&q_vec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(&q_vec) * inv_det;
+        if t <= t_min || t_max <= t {
+            return None;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        let normal = if self.mesh.normals.is_empty() {
+            edge1.cross(&edge2).normalize()
+        } else {
+            let n = &self.mesh.normals;
+            (b0 * n[i0] + b1 * n[i1] + b2 * n[i2]).normalize()
+        };
+        let (u, v) = if self.mesh.texcoords.is_empty() {
+            (b1, b2)
+        } else {
+            let uv = &self.mesh.texcoords;
+            let uv = b0 * uv[i0] + b1 * uv[i1] + b2 * uv[i2];
+            (uv.x, uv.y)
+        };
+
+        Some(HitRecord {
+            point: ray.at(t),
+            normal,
+            t,
+            u,
+            v,
+            material: self.mesh.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [i0, i1, i2] = self.vertices();
+        let (p0, p1, p2) = (
+            self.mesh.positions[i0],
+            self.mesh.positions[i1],
+            self.mesh.positions[i2],
+        );
+
+        Some(
+            Aabb {
+                min: p0.inf(&p1).inf(&p2),
+                max: p0.sup(&p1).sup(&p2),
+            }
+            .padded(BOX_PADDING),
+        )
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, _time: f32) -> f32 {
+        let Some(hit) = self.hit(&Ray::new(*origin, *direction), 0.001, f32::INFINITY) else {
+            return 0.0;
+        };
+
+        let [i0, i1, i2] = self.vertices();
+        let p0 = self.mesh.positions[i0];
+        let normal = (self.mesh.positions[i1] - p0).cross(&(self.mesh.positions[i2] - p0));
+        let area = 0.5 * normal.magnitude();
+        let distance_squared = hit.t * hit.t * direction.magnitude_squared();
+        let cosine = normal.normalize().dot(direction).abs() / direction.magnitude();
+
+        distance_squared / (cosine * area)
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(self.mesh.material())
+    }
+
+    fn random_direction(&self, origin: &Vec3, _time: f32, u: Vec2) -> Vec3 {
+        self.mesh.sample_point(self.index, u) - origin
+    }
+}
+
+/// An emissive mesh sampled as a single light, picking its triangles in proportion to their area.
+///
+/// Sampling every triangle as a light of its own would make each light sample and each bounce
+/// test all of them.
+pub struct MeshLight {
+    mesh: Arc<TriangleMesh>,
+    triangles: Bvh<Triangle>,
+    // Running sums of the areas of the triangles, in the order of the mesh.
+    areas: Vec<f32>,
+}
+
+impl MeshLight {
+    /// Fails on a mesh without triangles.
+    pub fn new(mesh: Arc<TriangleMesh>) -> Result<Self> {
+        if mesh.indices.is_empty() {
+            return Err(anyhow!("a light needs at least one triangle"));
+        }
+        let areas = (0..mesh.indices.len())
+            .scan(0.0, |sum, index| {
+                *sum += mesh.area(index);
+                Some(*sum)
+            })
+            .collect();
+        Ok(Self {
+            triangles: Bvh::new(mesh.triangles().collect())?,
+            mesh,
+            areas,
+        })
+    }
+
+    pub fn mesh(&self) -> &Arc<TriangleMesh> {
+        &self.mesh
+    }
+
+    fn area(&self) -> f32 {
+        self.areas.last().copied().unwrap_or(0.0)
+    }
+}
+
+impl Hittable for MeshLight {
+    #[inline(always)]
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.triangles.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.triangles.bounding_box()
+    }
+
+    // Every triangle the direction passes through adds the density of reaching it.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3, time: f32) -> f32 {
+        let area = self.area();
+        if area <= 0.0 {
+            return 0.0;
+        }
+        let mut pdf = 0.0;
+        self.triangles
+            .for_each_candidate(&Ray::new(*origin, *direction), |triangle| {
+                let triangle_pdf = triangle.pdf_value(origin, direction, time);
+                if triangle_pdf > 0.0 {
+                    pdf += triangle_pdf * self.mesh.area(triangle.index) / area;
+                }
+            });
+        pdf
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(self.mesh.material())
+    }
+
+    fn random_direction(&self, origin: &Vec3, _time: f32, u: Vec2) -> Vec3 {
+        let target = u.x * self.area();
+        let index = self
+            .areas
+            .partition_point(|&sum| sum <= target)
+            .min(self.areas.len() - 1);
+        // What is left of `u.x` picks the point within the triangle.
+        let start = if index == 0 {
+            0.0
+        } else {
+            self.areas[index - 1]
+        };
+        let width = self.areas[index] - start;
+        let ux = if width > 0.0 {
+            ((target - start) / width).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        self.mesh.sample_point(index, Vec2::new(ux, u.y)) - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::assert_light_sampling,
+        materials::{lambertian::Lambertian, metal::Metal},
+        vec::Color,
+    };
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Arc::new(Lambertian::new(Color::repeat(0.5))),
+        )
+    }
+
+    fn hit_from(origin: Vec3, direction: Vec3) -> Option<f32> {
+        triangle()
+            .hit(&Ray::new(origin, direction), 0.001, f32::INFINITY)
+            .map(|hit| hit.t)
+    }
+
+    #[test]
+    fn hits_inside() {
+        let triangle = triangle();
+        let hit = triangle
+            .hit(
+                &Ray::new(Vec3::new(0.5, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0)),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-6);
+        assert!((hit.point - Vec3::new(0.5, 0.5, 0.0)).norm() < 1e-6);
+        assert!((hit.normal - Vec3::z()).norm() < 1e-6);
+        // Barycentric coordinates of the second and third vertex.
+        assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn misses_outside() {
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        assert_eq!(hit_from(Vec3::new(1.5, 1.5, 2.0), down), None);
+        assert_eq!(hit_from(Vec3::new(-0.1, 0.5, 2.0), down), None);
+        // Behind the origin, or past `t_max`.
+        assert_eq!(hit_from(Vec3::new(0.5, 0.5, -2.0), down), None);
+        assert!(triangle()
+            .hit(&Ray::new(Vec3::new(0.5, 0.5, 2.0), down), 0.001, 1.5)
+            .is_none());
+        // Parallel to the plane of the triangle.
+        assert_eq!(
+            hit_from(Vec3::new(-1.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn hits_edges_and_corners() {
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        for point in [
+            Vec3::new(1.0, 0.0, 2.0),
+            Vec3::new(0.0, 1.0, 2.0),
+            Vec3::new(1.0, 1.0, 2.0),
+            Vec3::new(0.0, 0.0, 2.0),
+        ] {
+            assert!(hit_from(point, down).is_some(), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn samples_directions_with_their_pdf() {
+        assert_light_sampling(&triangle(), Vec3::new(0.5, 0.3, 1.0), 0.0);
+    }
+
+    #[test]
+    fn mesh_lights_sample_triangles_by_area() {
+        // An octahedron stretched along x, so its faces see the origin from many angles and
+        // directions through it cross two faces.
+        let positions = vec![
+            Vec3::new(-1.0, 0.0, -2.0),
+            Vec3::new(3.0, 0.0, -2.0),
+            Vec3::new(1.0, -1.0, -2.0),
+            Vec3::new(1.0, 1.0, -2.0),
+            Vec3::new(1.0, 0.0, -3.0),
+            Vec3::new(1.0, 0.0, -1.0),
+        ];
+        let mut indices = Vec::new();
+        for (a, b) in [(2, 4), (4, 3), (3, 5), (5, 2)] {
+            indices.push([0, a, b]);
+            indices.push([1, b, a]);
+        }
+        let mesh = TriangleMesh::new(
+            positions,
+            indices,
+            Arc::new(Lambertian::new(Color::repeat(0.5))),
+        );
+        let light = MeshLight::new(Arc::new(mesh)).unwrap();
+        assert_light_sampling(&light, Vec3::new(0.5, 0.2, 0.0), 0.0);
+    }
+
+    #[test]
+    fn hits_the_back_face() {
+        let triangle = triangle();
+        let hit = triangle
+            .hit(
+                &Ray::new(Vec3::new(0.5, 0.5, -3.0), Vec3::new(0.0, 0.0, 1.0)),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-6);
+        // The normal follows the winding, not the ray.
+        assert!((hit.normal - Vec3::z()).norm() < 1e-6);
+    }
+
+    #[test]
+    fn metal_reflects_off_the_back_face() {
+        let triangle = Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Arc::new(Metal::new(Color::repeat(0.8), 0.0)),
+        );
+        let ray = Ray::new(Vec3::new(0.5, -2.5, -3.0), Vec3::new(0.0, 1.0, 1.0));
+        let hit = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        let sample = hit
+            .material
+            .sample(&ray, &hit, 0.5, Vec2::new(0.5, 0.5))
+            .unwrap();
+        // Mirrored back to the side the ray came from.
+        let direction = sample.ray.direction().normalize();
+        assert!((direction - Vec3::new(0.0, 1.0, -1.0).normalize()).norm() < 1e-6);
+        assert_eq!(sample.attenuation, Color::repeat(0.8));
+    }
+}
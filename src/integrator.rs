@@ -0,0 +1,229 @@
+use std::cell::Cell;
+
+use crate::{
+    background::Background,
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+    sampler::Sampler,
+    vec::{Color, Vec2},
+};
+
+thread_local! {
+    static RAYS: Cell<u64> = const { Cell::new(0) };
+}
+
+// Rays traced by the current thread since the last call.
+pub fn take_ray_count() -> u64 {
+    RAYS.with(|rays| rays.replace(0))
+}
+
+#[inline(always)]
+fn count_ray() {
+    RAYS.with(|rays| rays.set(rays.get() + 1));
+}
+
+// Light and BSDF sampling are combined with multiple importance sampling, so emitters found by
+// either strategy are weighted by the power heuristic instead of being counted twice.
+//
+// Paths are extended bounce by bounce carrying their throughput. From `roulette_depth` bounces on,
+// Russian roulette ends paths with a chance that grows as their throughput drops and scales up
+// the survivors to make up for it, which keeps the estimate unbiased.
+pub fn radiance<H: Hittable>(
+    ray: &Ray,
+    world: &H,
+    lights: &[&dyn Hittable],
+    background: &Background,
+    max_depth: u32,
+    roulette_depth: u32,
+    sampler: &mut dyn Sampler,
+) -> Color {
+    let mut color = Color::zeros();
+    let mut throughput = Color::new(1.0, 1.0, 1.0);
+    let mut ray = *ray;
+    // Density of the BSDF sample that produced `ray`, `None` for camera and specular rays.
+    let mut bsdf_pdf: Option<f32> = None;
+
+    for depth in 0..max_depth {
+        count_ray();
+        let Some(hit) = world.hit(&ray, 0.001, f32::INFINITY) else {
+            color += throughput.component_mul(&background.color(&ray));
+            break;
+        };
+
+        // Every bounce takes the same dimensions whichever way it goes, so the dimensions of later
+        // bounces line up across the samples of a pixel.
+        let u_light = (sampler.get_1d(), sampler.get_2d());
+        let u_bsdf = (sampler.get_1d(), sampler.get_2d());
+        let u_roulette = sampler.get_1d();
+
+        let mut emitted = hit.material.emitted(hit.u, hit.v, &hit.point);
+        if let Some(bsdf_pdf) = bsdf_pdf {
+            if hit.material.is_emissive() && !lights.is_empty() {
+                emitted *= power_heuristic(bsdf_pdf, light_pdf(lights, &ray));
+            }
+        }
+        color += throughput.component_mul(&emitted);
+
+        let Some(sample) = hit.material.sample(&ray, &hit, u_bsdf.0, u_bsdf.1) else {
+            break;
+        };
+
+        if sample.specular {
+            bsdf_pdf = None;
+        } else {
+            color += throughput.component_mul(&sample_light(&ray, &hit, world, lights, u_light));
+            if sample.pdf <= 0.0 {
+                break;
+            }
+            bsdf_pdf = Some(sample.pdf);
+        }
+        throughput.component_mul_assign(&sample.attenuation);
+
+        if depth + 1 >= roulette_depth {
+            let survival = throughput.max().min(1.0);
+            if u_roulette >= survival {
+                break;
+            }
+            throughput /= survival;
+        }
+        ray = sample.ray;
+    }
+
+    color
+}
+
+fn sample_light<H: Hittable>(
+    ray: &Ray,
+    hit: &HitRecord,
+    world: &H,
+    lights: &[&dyn Hittable],
+    (uc, u): (f32, Vec2),
+) -> Color {
+    if lights.is_empty() {
+        return Color::zeros();
+    }
+
+    let light = lights[((uc * lights.len() as f32) as usize).min(lights.len() - 1)];
+    let shadow_ray = Ray::new(hit.point, light.random_direction(&hit.point, ray.time(), u))
+        .with_time(ray.time());
+    let light_pdf = light_pdf(lights, &shadow_ray);
+    if light_pdf <= 0.0 {
+        return Color::zeros();
+    }
+
+    let f = hit.material.evaluate(ray, hit, &shadow_ray.direction());
+    if f.max() <= 0.0 {
+        return Color::zeros();
+    }
+
+    // Whatever the shadow ray reaches first is what the light sample sees.
+    count_ray();
+    let Some(light_hit) = world.hit(&shadow_ray, 0.001, f32::INFINITY) else {
+        return Color::zeros();
+    };
+    let emitted = light_hit
+        .material
+        .emitted(light_hit.u, light_hit.v, &light_hit.point);
+    let bsdf_pdf = hit.material.pdf(ray, hit, &shadow_ray.direction());
+
+    power_heuristic(light_pdf, bsdf_pdf) / light_pdf * f.component_mul(&emitted)
+}
+
+#[inline(always)]
+fn light_pdf(lights: &[&dyn Hittable], ray: &Ray) -> f32 {
+    let sum: f32 = lights
+        .iter()
+        .map(|light| light.pdf_value(&ray.origin(), &ray.direction(), ray.time()))
+        .sum();
+    sum / lights.len() as f32
+}
+
+#[inline(always)]
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b <= 0.0 {
+        return 0.0;
+    }
+    a / (a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        hittable::{bvh::Bvh, sphere::Sphere},
+        materials::{diffuse_light::DiffuseLight, lambertian::Lambertian},
+        sampler::{independent::IndependentSampler, warp::uniform_sphere},
+        textures::solid_color::SolidColor,
+        vec::Vec3,
+    };
+
+    const PATHS: u32 = 20_000;
+    const MAX_DEPTH: u32 = 64;
+
+    // Mean and standard error of the luminance of paths leaving the centre of a closed room, a
+    // diffuse sphere with a small light inside, and the rays they traced.
+    fn estimate(roulette_depth: u32, seed: u64) -> (f64, f64, u64) {
+        let room: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Vec3::zeros(),
+            10.0,
+            Lambertian::new(Color::repeat(0.7)),
+        ));
+        let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 5.0, 0.0),
+            1.0,
+            DiffuseLight::new(Arc::new(SolidColor::new(Color::repeat(4.0)))),
+        ));
+        let world = Bvh::new(vec![Arc::clone(&room), Arc::clone(&light)]).unwrap();
+        let lights = [light.as_ref()];
+
+        let mut sampler = IndependentSampler::new(seed);
+        let (mut sum, mut sum_squares) = (0.0, 0.0);
+        take_ray_count();
+        for i in 0..PATHS {
+            sampler.start_sample(0, 0, i);
+            let ray = Ray::new(Vec3::zeros(), uniform_sphere(sampler.get_2d()));
+            let color = radiance(
+                &ray,
+                &world,
+                &lights,
+                &Background::None,
+                MAX_DEPTH,
+                roulette_depth,
+                &mut sampler,
+            );
+            let value = color.mean() as f64;
+            sum += value;
+            sum_squares += value * value;
+        }
+        let n = PATHS as f64;
+        let mean = sum / n;
+        let variance = (sum_squares / n - mean * mean).max(0.0);
+        (mean, (variance / n).sqrt(), take_ray_count())
+    }
+
+    #[test]
+    fn russian_roulette_keeps_the_mean() {
+        // Roulette from the first bounce, and never before the depth limit.
+        let (early, early_error, early_rays) = estimate(1, 3);
+        let (late, late_error, late_rays) = estimate(MAX_DEPTH, 3);
+        let tolerance = 4.0 * early_error.hypot(late_error);
+        assert!(
+            (early - late).abs() < tolerance,
+            "{} ± {} against {} ± {}",
+            early,
+            early_error,
+            late,
+            late_error
+        );
+        assert!(early_rays < late_rays, "{} {}", early_rays, late_rays);
+    }
+
+    #[test]
+    fn russian_roulette_is_deterministic() {
+        assert_eq!(estimate(1, 5), estimate(1, 5));
+        assert_ne!(estimate(1, 5).0, estimate(1, 6).0);
+    }
+}
@@ -1,23 +1,23 @@
-use rand::rngs::SmallRng;
+use std::sync::Arc;
 
 use crate::{
     hittable::HitRecord,
-    vec::{random_vec, Color, Vec3},
+    vec::{Color, Vec2, Vec3},
     Ray,
 };
 
 pub mod dielectric;
+pub mod diffuse_light;
 pub mod lambertian;
 pub mod metal;
 
+// Normal on the side the ray arrives from, so back faces of open meshes scatter correctly.
 #[inline(always)]
-fn random_in_unit_sphere(rng: &mut SmallRng) -> Vec3 {
-    let unit = Vec3::new(1.0, 1.0, 1.0);
-    loop {
-        let p = 2.0 * random_vec(rng, 0.0..=1.0) - unit;
-        if p.magnitude_squared() < 1.0 {
-            return p;
-        }
+fn facing_normal(ray: &Ray, hit: &HitRecord) -> Vec3 {
+    if ray.direction().dot(&hit.normal) > 0.0 {
+        -hit.normal
+    } else {
+        hit.normal
     }
 }
 
@@ -51,6 +51,108 @@ fn schlick(cosine: f32, ref_idx: f32) -> f32 {
     }
 }
 
-pub trait Material {
-    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut SmallRng) -> Option<(Ray, Color)>;
+pub struct BsdfSample {
+    pub ray: Ray,
+    // Throughput of the sampled path: `evaluate / pdf`, or the lobe's colour for specular samples.
+    pub attenuation: Color,
+    pub pdf: f32,
+    // Delta lobes can't be hit by light sampling, so they are excluded from MIS.
+    pub specular: bool,
+}
+
+pub trait Material: Send + Sync {
+    // `uc` and `u` are uniform samples, for choosing a lobe and a direction in it.
+    fn sample(&self, ray: &Ray, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample>;
+
+    // BSDF times the cosine term for light leaving along `direction`, zero for specular lobes.
+    fn evaluate(&self, _ray: &Ray, _hit: &HitRecord, _direction: &Vec3) -> Color {
+        Color::zeros()
+    }
+
+    // Solid angle density with which `sample` picks `direction`, zero for specular lobes.
+    fn pdf(&self, _ray: &Ray, _hit: &HitRecord, _direction: &Vec3) -> f32 {
+        0.0
+    }
+
+    fn emitted(&self, _u: f32, _v: f32, _point: &Vec3) -> Color {
+        Color::zeros()
+    }
+
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // Fraction of light reflected, for the albedo output layer.
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        Color::zeros()
+    }
+
+    // Tells materials apart, materials shared through an `Arc` have the same identity.
+    fn identity(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
+}
+
+impl<M: Material + ?Sized> Material for Arc<M> {
+    #[inline(always)]
+    fn sample(&self, ray: &Ray, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
+        self.as_ref().sample(ray, hit, uc, u)
+    }
+
+    #[inline(always)]
+    fn evaluate(&self, ray: &Ray, hit: &HitRecord, direction: &Vec3) -> Color {
+        self.as_ref().evaluate(ray, hit, direction)
+    }
+
+    #[inline(always)]
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: &Vec3) -> f32 {
+        self.as_ref().pdf(ray, hit, direction)
+    }
+
+    #[inline(always)]
+    fn emitted(&self, u: f32, v: f32, point: &Vec3) -> Color {
+        self.as_ref().emitted(u, v, point)
+    }
+
+    #[inline(always)]
+    fn is_emissive(&self) -> bool {
+        self.as_ref().is_emissive()
+    }
+
+    #[inline(always)]
+    fn albedo(&self, hit: &HitRecord) -> Color {
+        self.as_ref().albedo(hit)
+    }
+
+    #[inline(always)]
+    fn identity(&self) -> usize {
+        self.as_ref().identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::{sphere::Sphere, Hittable},
+        materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal},
+    };
+
+    #[test]
+    fn scattered_rays_keep_the_time() {
+        let materials: [Arc<dyn Material>; 3] = [
+            Arc::new(Lambertian::new(Color::repeat(0.5))),
+            Arc::new(Metal::new(Color::repeat(0.5), 0.3)),
+            Arc::new(Dielectric::new(1.5)),
+        ];
+        for material in materials {
+            let sphere = Sphere::new(Vec3::zeros(), 1.0, material);
+            let ray = Ray::new(Vec3::new(0.3, 0.2, 5.0), Vec3::new(0.0, 0.0, -1.0)).with_time(0.37);
+            let hit = sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            for (uc, u) in [(0.1, Vec2::new(0.2, 0.7)), (0.9, Vec2::new(0.8, 0.1))] {
+                let sample = hit.material.sample(&ray, &hit, uc, u).unwrap();
+                assert_eq!(sample.ray.time(), 0.37);
+            }
+        }
+    }
 }
@@ -1,11 +1,10 @@
 use crate::{
     hittable::HitRecord,
     ray::Ray,
-    vec::{Color, Vec3},
+    vec::{Color, Vec2},
 };
-use rand::{rngs::SmallRng, Rng};
 
-use super::{reflect, refract, schlick, Material};
+use super::{reflect, refract, schlick, BsdfSample, Material};
 
 pub struct Dielectric {
     ref_idx: f32,
@@ -21,8 +20,8 @@ impl Dielectric {
 
 impl Material for Dielectric {
     #[inline(always)]
-    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut SmallRng) -> Option<(Ray, Color)> {
-        let attenuation = Vec3::new(1.0, 1.0, 1.0);
+    fn sample(&self, ray: &Ray, hit: &HitRecord, uc: f32, _u: Vec2) -> Option<BsdfSample> {
+        let attenuation = Color::new(1.0, 1.0, 1.0);
         let (outward_normal, ni_over_nt, cosine) = if ray.direction().dot(&hit.normal) > 0.0 {
             let cosine =
                 self.ref_idx * ray.direction().dot(&hit.normal) / ray.direction().magnitude();
@@ -33,14 +32,27 @@ impl Material for Dielectric {
         };
         if let Some(refracted) = refract(&ray.direction(), &outward_normal, ni_over_nt) {
             let refract_prob = schlick(cosine, self.ref_idx);
-            if rng.gen::<f32>() >= refract_prob {
-                let scattered = Ray::new(hit.point, refracted);
-                return Some((scattered, attenuation));
+            if uc >= refract_prob {
+                return Some(BsdfSample {
+                    ray: Ray::new(hit.point, refracted).with_time(ray.time()),
+                    attenuation,
+                    pdf: 0.0,
+                    specular: true,
+                });
             }
         }
 
         let reflected = reflect(&ray.direction(), &hit.normal);
-        let scattered = Ray::new(hit.point, reflected);
-        Some((scattered, attenuation))
+        Some(BsdfSample {
+            ray: Ray::new(hit.point, reflected).with_time(ray.time()),
+            attenuation,
+            pdf: 0.0,
+            specular: true,
+        })
+    }
+
+    #[inline(always)]
+    fn albedo(&self, _hit: &HitRecord) -> Color {
+        Color::new(1.0, 1.0, 1.0)
     }
 }
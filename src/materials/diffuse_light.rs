@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use crate::{
+    hittable::HitRecord,
+    ray::Ray,
+    textures::Texture,
+    vec::{Color, Vec2, Vec3},
+};
+
+use super::{BsdfSample, Material};
+
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Arc<dyn Texture>) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    #[inline(always)]
+    fn sample(&self, _: &Ray, _: &HitRecord, _: f32, _: Vec2) -> Option<BsdfSample> {
+        None
+    }
+
+    #[inline(always)]
+    fn emitted(&self, u: f32, v: f32, point: &Vec3) -> Color {
+        self.emit.value(u, v, point)
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
@@ -1,25 +1,58 @@
-use rand::rngs::SmallRng;
+use std::{f32::consts::PI, sync::Arc};
 
-use crate::{hittable::HitRecord, ray::Ray, vec::Color};
+use crate::{
+    hittable::HitRecord,
+    ray::Ray,
+    sampler::warp::cosine_hemisphere,
+    textures::{solid_color::SolidColor, Texture},
+    vec::{orthonormal_basis, Color, Vec2, Vec3},
+};
 
-use super::{random_in_unit_sphere, Material};
+use super::{facing_normal, BsdfSample, Material};
 
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
 
 impl Material for Lambertian {
     #[inline(always)]
-    fn scatter(&self, _: &Ray, hit: &HitRecord, rng: &mut SmallRng) -> Option<(Ray, Color)> {
-        let target = hit.point + hit.normal + random_in_unit_sphere(rng);
-        let scattered = Ray::new(hit.point, target - hit.point);
+    fn sample(&self, ray: &Ray, hit: &HitRecord, _uc: f32, u: Vec2) -> Option<BsdfSample> {
+        let normal = facing_normal(ray, hit).normalize();
+        let (tangent, bitangent) = orthonormal_basis(&normal);
+        let local = cosine_hemisphere(u);
+        let direction = (local.x * tangent + local.y * bitangent + local.z * normal).normalize();
+
+        Some(BsdfSample {
+            ray: Ray::new(hit.point, direction).with_time(ray.time()),
+            attenuation: self.albedo.value(hit.u, hit.v, &hit.point),
+            pdf: normal.dot(&direction).max(0.0) / PI,
+            specular: false,
+        })
+    }
 
-        Some((scattered, self.albedo))
+    #[inline(always)]
+    fn evaluate(&self, ray: &Ray, hit: &HitRecord, direction: &Vec3) -> Color {
+        let cosine = facing_normal(ray, hit).dot(&direction.normalize()).max(0.0);
+        self.albedo.value(hit.u, hit.v, &hit.point) * (cosine / PI)
+    }
+
+    #[inline(always)]
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: &Vec3) -> f32 {
+        facing_normal(ray, hit).dot(&direction.normalize()).max(0.0) / PI
+    }
+
+    #[inline(always)]
+    fn albedo(&self, hit: &HitRecord) -> Color {
+        self.albedo.value(hit.u, hit.v, &hit.point)
     }
 }
@@ -1,16 +1,26 @@
-use rand::rngs::SmallRng;
+use std::sync::Arc;
 
-use crate::{hittable::HitRecord, ray::Ray, vec::Color};
+use crate::{
+    hittable::HitRecord,
+    ray::Ray,
+    sampler::warp::uniform_ball,
+    textures::{solid_color::SolidColor, Texture},
+    vec::{Color, Vec2},
+};
 
-use super::{random_in_unit_sphere, reflect, Material};
+use super::{facing_normal, reflect, BsdfSample, Material};
 
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzzy: f32,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzzy: f32) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzzy)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzzy: f32) -> Self {
         Self {
             albedo,
             fuzzy: fuzzy.min(1.0),
@@ -20,16 +30,25 @@ impl Metal {
 
 impl Material for Metal {
     #[inline(always)]
-    fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut SmallRng) -> Option<(Ray, Color)> {
-        let reflected = reflect(&ray.direction().normalize(), &hit.normal);
-        if reflected.dot(&hit.normal) > 0.0 {
-            let scattered = Ray::new(
-                hit.point,
-                reflected + self.fuzzy * random_in_unit_sphere(rng),
-            );
-            Some((scattered, self.albedo))
+    fn sample(&self, ray: &Ray, hit: &HitRecord, uc: f32, u: Vec2) -> Option<BsdfSample> {
+        let normal = facing_normal(ray, hit);
+        let reflected = reflect(&ray.direction().normalize(), &normal);
+        if reflected.dot(&normal) > 0.0 {
+            // Fuzzy reflections are still treated as a delta lobe, light sampling can't help them.
+            Some(BsdfSample {
+                ray: Ray::new(hit.point, reflected + self.fuzzy * uniform_ball(uc, u))
+                    .with_time(ray.time()),
+                attenuation: self.albedo.value(hit.u, hit.v, &hit.point),
+                pdf: 0.0,
+                specular: true,
+            })
         } else {
             None
         }
     }
+
+    #[inline(always)]
+    fn albedo(&self, hit: &HitRecord) -> Color {
+        self.albedo.value(hit.u, hit.v, &hit.point)
+    }
 }
@@ -0,0 +1,222 @@
+use anyhow::{anyhow, Context, Result};
+use image::{codecs::hdr::HdrEncoder, ImageFormat};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    framebuffer::Framebuffer,
+    tonemap::{tonemap, ToneMapping},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    Hdr,
+    Exr,
+    Pfm,
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "png" => Ok(Self::Png),
+            "jpg" | "jpeg" => Ok(Self::Jpeg),
+            "hdr" => Ok(Self::Hdr),
+            "exr" => Ok(Self::Exr),
+            "pfm" => Ok(Self::Pfm),
+            _ => Err(anyhow!(
+                "unsupported output format for `{}`, expected png, jpg, hdr, exr or pfm",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Writes the framebuffer in the format given by the extension of `path`.
+///
+/// `.hdr`, `.exr` and `.pfm` keep the linear radiance, `.png` and `.jpg` are tone mapped to 8-bit sRGB.
+pub fn save(framebuffer: &Framebuffer, path: &Path, tone_mapping: &ToneMapping) -> Result<()> {
+    match OutputFormat::from_path(path)? {
+        OutputFormat::Png => tonemap(framebuffer, tone_mapping)
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(Into::into),
+        OutputFormat::Jpeg => tonemap(framebuffer, tone_mapping)
+            .save_with_format(path, ImageFormat::Jpeg)
+            .map_err(Into::into),
+        OutputFormat::Hdr => write_hdr(framebuffer, path),
+        OutputFormat::Exr => framebuffer
+            .to_rgb32f()
+            .save_with_format(path, ImageFormat::OpenExr)
+            .map_err(Into::into),
+        OutputFormat::Pfm => write_pfm(framebuffer, path),
+    }
+    .with_context(|| format!("failed to write `{}`", path.display()))
+}
+
+fn write_hdr(framebuffer: &Framebuffer, path: &Path) -> Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    let image = framebuffer.to_rgb32f();
+    let pixels: Vec<_> = image.pixels().copied().collect();
+    HdrEncoder::new(writer).encode(
+        &pixels,
+        framebuffer.width() as usize,
+        framebuffer.height() as usize,
+    )?;
+
+    Ok(())
+}
+
+// Portable float map: a small text header, then little-endian floats with the bottom row first.
+fn write_pfm(framebuffer: &Framebuffer, path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write!(
+        writer,
+        "PF\n{} {}\n-1.0\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    for row in framebuffer
+        .pixels()
+        .chunks(framebuffer.width() as usize)
+        .rev()
+    {
+        for c in row.iter().flat_map(|c| c.iter()) {
+            writer.write_all(&c.to_le_bytes())?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{codecs::hdr::HdrDecoder, Rgb};
+    use std::{fs, io::BufReader, path::PathBuf};
+
+    use super::*;
+    use crate::vec::Color;
+
+    // Values above 1 whose mantissas fit in the 8 bits of a Radiance pixel, so every format keeps
+    // them exactly.
+    fn framebuffer() -> Framebuffer {
+        Framebuffer::from_pixels(
+            3,
+            2,
+            vec![
+                Color::new(4.0, 3.0, 0.25),
+                Color::new(1.5, 0.5, 0.125),
+                Color::zeros(),
+                Color::new(16.0, 8.0, 2.0),
+                Color::new(0.75, 0.25, 0.0625),
+                Color::new(2.5, 1.0, 0.0),
+            ],
+        )
+    }
+
+    fn output_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("output-{}-{}", std::process::id(), name))
+    }
+
+    // Saves the test framebuffer as `name` and reads its pixels back with `read`.
+    fn round_trip(name: &str, read: impl FnOnce(&Path) -> Vec<Rgb<f32>>) {
+        let framebuffer = framebuffer();
+        let path = output_path(name);
+        save(&framebuffer, &path, &ToneMapping::default()).unwrap();
+        let pixels = read(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(pixels.len(), 3 * 2);
+        for (i, p) in pixels.iter().enumerate() {
+            let (x, y) = (i as u32 % 3, i as u32 / 3);
+            assert_eq!(
+                Color::new(p[0], p[1], p[2]),
+                framebuffer.get(x, y),
+                "{} {}",
+                x,
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn hdr_keeps_linear_values() {
+        round_trip("linear.hdr", |path| {
+            // `image::open` would convert Radiance files to 8 bits.
+            let decoder = HdrDecoder::new(BufReader::new(File::open(path).unwrap())).unwrap();
+            let metadata = decoder.metadata();
+            assert_eq!((metadata.width, metadata.height), (3, 2));
+            decoder.read_image_hdr().unwrap()
+        });
+    }
+
+    #[test]
+    fn exr_keeps_linear_values() {
+        round_trip("linear.exr", |path| {
+            let image = image::open(path).unwrap().to_rgb32f();
+            assert_eq!(image.dimensions(), (3, 2));
+            image.pixels().copied().collect()
+        });
+    }
+
+    #[test]
+    fn pfm_stores_bottom_row_first() {
+        let framebuffer = framebuffer();
+        let path = output_path("linear.pfm");
+        save(&framebuffer, &path, &ToneMapping::default()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // A negative scale means little-endian.
+        let header = b"PF\n3 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let floats: Vec<f32> = bytes[header.len()..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(floats.len(), 3 * 2 * 3);
+        for (i, c) in floats.chunks_exact(3).enumerate() {
+            let (x, y) = (i as u32 % 3, 1 - i as u32 / 3);
+            assert_eq!(
+                Color::new(c[0], c[1], c[2]),
+                framebuffer.get(x, y),
+                "{} {}",
+                x,
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        for (name, format) in [
+            ("out.png", OutputFormat::Png),
+            ("out.jpg", OutputFormat::Jpeg),
+            ("out.jpeg", OutputFormat::Jpeg),
+            ("out.hdr", OutputFormat::Hdr),
+            ("out.exr", OutputFormat::Exr),
+            ("out.pfm", OutputFormat::Pfm),
+            ("dir/OUT.EXR", OutputFormat::Exr),
+        ] {
+            assert_eq!(OutputFormat::from_path(Path::new(name)).unwrap(), format);
+        }
+        for name in ["out.tga", "out", "out.png.bak"] {
+            let error = OutputFormat::from_path(Path::new(name)).unwrap_err();
+            assert!(
+                error.to_string().contains("unsupported output format"),
+                "{}",
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::tiles::TileStats;
+
+/// How far a render got, as reported to a [`ProgressSink`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Progress {
+    /// Passes over the whole image completed so far.
+    pub passes_done: u32,
+    /// Tiles done in the current pass.
+    pub tiles_done: u32,
+    pub total_tiles: u32,
+    pub samples_done: u64,
+    /// Zero when the render has no sample count and runs until a time or noise limit.
+    pub total_samples: u64,
+    pub rays: u64,
+    /// Mean relative standard error of the pixels, once every pixel has two samples.
+    pub noise: Option<f32>,
+    pub elapsed: Duration,
+    pub time_limit: Option<Duration>,
+}
+
+impl Progress {
+    /// Whichever of the sample count and the time limit is closer to being reached.
+    pub fn fraction(&self) -> f64 {
+        let samples =
+            (self.total_samples > 0).then(|| self.samples_done as f64 / self.total_samples as f64);
+        let time = self
+            .time_limit
+            .map(|limit| self.elapsed.as_secs_f64() / limit.as_secs_f64().max(f64::EPSILON));
+        match (samples, time) {
+            (Some(samples), Some(time)) => samples.max(time).min(1.0),
+            (Some(fraction), None) | (None, Some(fraction)) => fraction.min(1.0),
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Remaining time, extrapolated from the rate so far. Unknown for renders that only stop at
+    /// a noise threshold.
+    pub fn eta(&self) -> Option<Duration> {
+        let fraction = self.fraction();
+        if fraction <= 0.0 {
+            return None;
+        }
+        Some(self.elapsed.mul_f64((1.0 - fraction) / fraction))
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+        self.rays as f64 / seconds
+    }
+}
+
+/// Receives progress from a render. Updates come from the worker threads, so implementations
+/// should be cheap and throttle their own output.
+pub trait ProgressSink: Send + Sync {
+    fn start(&self, _progress: &Progress) {}
+    fn update(&self, progress: &Progress);
+    fn finish(&self, _progress: &Progress) {}
+    /// Called once per finished tile, before the `update` that counts it.
+    fn tile_finished(&self, _stats: &TileStats) {}
+    /// Informational lines, such as statistics about the scene.
+    fn message(&self, _message: &str) {}
+}
+
+impl<P: ProgressSink + ?Sized> ProgressSink for Box<P> {
+    fn start(&self, progress: &Progress) {
+        self.as_ref().start(progress)
+    }
+
+    fn update(&self, progress: &Progress) {
+        self.as_ref().update(progress)
+    }
+
+    fn finish(&self, progress: &Progress) {
+        self.as_ref().finish(progress)
+    }
+
+    fn tile_finished(&self, stats: &TileStats) {
+        self.as_ref().tile_finished(stats)
+    }
+
+    fn message(&self, message: &str) {
+        self.as_ref().message(message)
+    }
+}
+
+/// Reports nothing.
+pub struct SilentProgress;
+
+impl ProgressSink for SilentProgress {
+    fn update(&self, _progress: &Progress) {}
+}
+
+// The bar follows `Progress::fraction`, which may be driven by time rather than samples.
+const BAR_LENGTH: u64 = 10_000;
+
+/// A single progress bar on the terminal.
+pub struct IndicatifProgress {
+    bar: ProgressBar,
+}
+
+impl Default for IndicatifProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndicatifProgress {
+    pub fn new() -> Self {
+        let bar = ProgressBar::new(0);
+        bar.set_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] {wide_bar:.cyan/blue} {percent:>3}% ETA {eta} {msg}",
+            )
+            .expect("valid progress template")
+            .progress_chars("##-"),
+        );
+        Self { bar }
+    }
+}
+
+impl ProgressSink for IndicatifProgress {
+    fn start(&self, _progress: &Progress) {
+        self.bar.set_length(BAR_LENGTH);
+        self.bar.reset();
+        self.bar.println("✨ Generating...");
+    }
+
+    fn update(&self, progress: &Progress) {
+        self.bar
+            .set_position((progress.fraction() * BAR_LENGTH as f64) as u64);
+        let mut message = format!(
+            "pass {}, {:.2} Mrays/s",
+            progress.passes_done + 1,
+            progress.rays_per_second() / 1e6
+        );
+        if let Some(noise) = progress.noise {
+            message += &format!(", noise {:.4}", noise);
+        }
+        self.bar.set_message(message);
+    }
+
+    fn finish(&self, progress: &Progress) {
+        self.update(progress);
+        self.bar.println("🍻 Done!!");
+        self.bar.abandon();
+    }
+
+    fn message(&self, message: &str) {
+        self.bar.println(message);
+    }
+}
+
+/// Plain log lines on stderr at most once per `interval`, for services and CI logs.
+pub struct LogProgress {
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+impl Default for LogProgress {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5))
+    }
+}
+
+impl LogProgress {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    fn log(progress: &Progress) {
+        let eta = progress
+            .eta()
+            .map_or_else(|| "?".to_string(), |eta| format!("{:.0?}", eta));
+        let noise = progress
+            .noise
+            .map_or_else(|| "?".to_string(), |noise| format!("{:.4}", noise));
+        eprintln!(
+            "[{:.1?}] {:5.1}% pass {} tiles {}/{}, {:.2} Mrays/s, noise {}, ETA {}",
+            progress.elapsed,
+            100.0 * progress.fraction(),
+            progress.passes_done + 1,
+            progress.tiles_done,
+            progress.total_tiles,
+            progress.rays_per_second() / 1e6,
+            noise,
+            eta
+        );
+    }
+}
+
+impl ProgressSink for LogProgress {
+    fn update(&self, progress: &Progress) {
+        let now = Instant::now();
+        let mut last = self.last.lock().unwrap();
+        if !matches!(*last, Some(last) if now - last < self.interval) {
+            *last = Some(now);
+            Self::log(progress);
+        }
+    }
+
+    fn finish(&self, progress: &Progress) {
+        Self::log(progress);
+    }
+
+    fn message(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(samples_done: u64, total_samples: u64, elapsed: u64) -> Progress {
+        Progress {
+            samples_done,
+            total_samples,
+            elapsed: Duration::from_secs(elapsed),
+            ..Progress::default()
+        }
+    }
+
+    #[test]
+    fn fraction_of_the_samples() {
+        assert_eq!(progress(0, 100, 0).fraction(), 0.0);
+        assert_eq!(progress(25, 100, 10).fraction(), 0.25);
+        assert_eq!(progress(100, 100, 40).fraction(), 1.0);
+        // Without any limit there is nothing to measure against.
+        assert_eq!(progress(50, 0, 10).fraction(), 0.0);
+    }
+
+    #[test]
+    fn fraction_follows_the_closer_limit() {
+        let timed = |samples_done, elapsed| Progress {
+            time_limit: Some(Duration::from_secs(100)),
+            ..progress(samples_done, 100, elapsed)
+        };
+        assert_eq!(timed(10, 50).fraction(), 0.5);
+        assert_eq!(timed(80, 50).fraction(), 0.8);
+        // Overrunning a limit still counts as done, not more.
+        assert_eq!(timed(10, 150).fraction(), 1.0);
+        let time_only = Progress {
+            total_samples: 0,
+            ..timed(10, 25)
+        };
+        assert_eq!(time_only.fraction(), 0.25);
+    }
+
+    #[test]
+    fn eta_extrapolates_the_rate_so_far() {
+        assert_eq!(progress(0, 100, 0).eta(), None);
+        assert_eq!(progress(0, 100, 10).eta(), None);
+        assert_eq!(progress(25, 100, 10).eta(), Some(Duration::from_secs(30)));
+        assert_eq!(progress(100, 100, 40).eta(), Some(Duration::ZERO));
+        // Renders stopped only by a noise threshold can't tell.
+        assert_eq!(progress(50, 0, 10).eta(), None);
+    }
+
+    #[test]
+    fn rays_per_second() {
+        let progress = Progress {
+            rays: 3_000_000,
+            elapsed: Duration::from_millis(1500),
+            ..Progress::default()
+        };
+        assert_eq!(progress.rays_per_second(), 2_000_000.0);
+        assert_eq!(Progress::default().rays_per_second(), 0.0);
+    }
+}
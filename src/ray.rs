@@ -1,13 +1,24 @@
 use crate::vec::Vec3;
 
+#[derive(Copy, Clone, Debug)]
 pub struct Ray {
     origin: Vec3,
     direction: Vec3,
+    time: f32,
 }
 
 impl Ray {
     pub fn new(origin: Vec3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+    /// The ray at a point in time of the camera's shutter interval, for moving objects.
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
     }
     #[inline(always)]
     pub fn at(&self, t: f32) -> Vec3 {
@@ -21,4 +32,8 @@ impl Ray {
     pub fn direction(&self) -> Vec3 {
         self.direction
     }
+    #[inline(always)]
+    pub fn time(&self) -> f32 {
+        self.time
+    }
 }
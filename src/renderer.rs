@@ -0,0 +1,744 @@
+use anyhow::{anyhow, Result};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    aov::{first_hit, Aov, MaterialIds},
+    cancel::CancellationToken,
+    crop::CropWindow,
+    film::{Film, PixelStats, SplatTile},
+    filter::Filter,
+    framebuffer::Framebuffer,
+    heatmap::SampleCounts,
+    hittable::{bvh::BvhTree, Hittable},
+    integrator::{radiance, take_ray_count},
+    progress::{Progress, ProgressSink, SilentProgress},
+    sampler::{Sampler, SamplerKind},
+    scene::Scene,
+    tiles::{tiles, Tile, TileOrder, TileStats},
+    vec::Color,
+};
+
+// Two-sided 95% confidence, pixels stop once the interval of their mean is within the threshold.
+const CONFIDENCE_Z: f32 = 1.96;
+
+/// Keeps sampling only the pixels whose mean is still uncertain.
+#[derive(Copy, Clone, Debug)]
+pub struct AdaptiveSampling {
+    /// Samples every pixel takes before it may stop, so the variance estimate can be trusted.
+    pub min_samples: u32,
+    /// Half width of the 95% confidence interval of a pixel's luminance, relative to the
+    /// luminance, below which the pixel stops.
+    pub threshold: f32,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        Self {
+            min_samples: 16,
+            threshold: 0.05,
+        }
+    }
+}
+
+impl AdaptiveSampling {
+    fn converged(&self, stats: &PixelStats) -> bool {
+        stats.samples() >= self.min_samples
+            && stats
+                .relative_error()
+                .is_some_and(|error| CONFIDENCE_Z * error <= self.threshold)
+    }
+}
+
+/// Image size and quality of a render.
+#[derive(Clone, Debug)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    /// Samples to take in every pixel, or at most with adaptive sampling. Zero keeps going until
+    /// another limit is reached.
+    pub samples_per_pixel: u32,
+    /// Maximum number of bounces of a path.
+    pub max_depth: u32,
+    /// Bounces after which paths are ended at random depending on their throughput, instead of
+    /// always going on to `max_depth`.
+    pub roulette_depth: u32,
+    /// Renders with the same seed and settings produce identical framebuffers.
+    pub seed: u64,
+    /// Edge length of the square tiles the image is split into.
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    /// Renders only this part of the image, with the same pixels as a full render up to rounding
+    /// in the sums of filters wider than a pixel.
+    pub crop: Option<CropWindow>,
+    /// Keeps the framebuffer at the full image size with black outside the crop window, instead
+    /// of cutting it down to the window.
+    pub crop_full_frame: bool,
+    /// Samples added to every pixel per pass over the image. Limits are checked between passes.
+    pub samples_per_pass: u32,
+    /// Stops rendering, possibly in the middle of a pass, once this much time has passed.
+    pub time_limit: Option<Duration>,
+    /// Stops after the pass that brings the mean relative standard error of the pixels below this.
+    pub noise_threshold: Option<f32>,
+    /// Stops sampling pixels once they have converged, instead of giving them all the same count.
+    pub adaptive: Option<AdaptiveSampling>,
+    /// Where the sample points of pixel positions, the lens and every bounce come from.
+    pub sampler: SamplerKind,
+    /// How samples are weighted into the pixels around them.
+    pub filter: Filter,
+    /// What the main image shows, the path traced beauty image or a diagnostic layer.
+    pub integrator: Aov,
+    /// Extra layers rendered alongside the main image from the same camera rays.
+    pub aovs: Vec<Aov>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 128,
+            samples_per_pixel: 500,
+            max_depth: 50,
+            roulette_depth: 5,
+            seed: 0,
+            tile_size: 32,
+            tile_order: TileOrder::default(),
+            crop: None,
+            crop_full_frame: false,
+            samples_per_pass: 4,
+            time_limit: None,
+            noise_threshold: None,
+            adaptive: None,
+            sampler: SamplerKind::default(),
+            filter: Filter::default(),
+            integrator: Aov::default(),
+            aovs: Vec::new(),
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Width over height, the aspect ratio cameras for these settings should be created with.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    // Samples the next pass adds to a pixel, zero once it is done.
+    fn pass_samples(&self, stats: &PixelStats) -> u32 {
+        if self
+            .adaptive
+            .is_some_and(|adaptive| adaptive.converged(stats))
+        {
+            return 0;
+        }
+        let per_pass = self.samples_per_pass.max(1);
+        match self.samples_per_pixel {
+            0 => per_pass,
+            max => per_pass.min(max.saturating_sub(stats.samples())),
+        }
+    }
+}
+
+type PreviewFn = dyn Fn(&Framebuffer) + Send + Sync;
+
+/// Everything a render produces.
+pub struct RenderOutput {
+    /// The image of [`RenderSettings::integrator`].
+    pub framebuffer: Framebuffer,
+    pub sample_counts: SampleCounts,
+    /// The layers of [`RenderSettings::aovs`], in the same order.
+    pub aovs: Vec<(Aov, Framebuffer)>,
+}
+
+/// Path traces a [`Scene`] into a [`Framebuffer`] of linear radiance.
+///
+/// The image is refined in passes over all of its tiles until the sample count, time limit or
+/// noise threshold of the [`RenderSettings`] is reached, or the render is cancelled.
+///
+/// ```no_run
+/// use ray_tracing_one_weekend::{
+///     progress::LogProgress, scene::random_scene, RenderSettings, Renderer,
+/// };
+///
+/// let settings = RenderSettings::default();
+/// let scene = random_scene(settings.aspect_ratio(), settings.seed);
+/// let framebuffer = Renderer::new()
+///     .with_progress(LogProgress::default())
+///     .render(&scene, &settings)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct Renderer {
+    progress: Box<dyn ProgressSink>,
+    cancellation: CancellationToken,
+    preview: Option<(Duration, Box<PreviewFn>)>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Shared between the threads of a render.
+struct RenderState<'a> {
+    settings: &'a RenderSettings,
+    scene: &'a Scene,
+    world: BvhTree<'a>,
+    lights: Vec<&'a dyn Hittable>,
+    material_ids: MaterialIds,
+    start: Instant,
+    // The pixels that are sampled, the output region and the pixels whose samples reach it.
+    sampled: Tile,
+    tiles: Vec<Tile>,
+    passes_done: u32,
+    noise: Option<f32>,
+    tiles_done: AtomicU32,
+    samples_done: AtomicU64,
+    rays: AtomicU64,
+    total_samples: u64,
+}
+
+impl<'a> RenderState<'a> {
+    fn progress(&self) -> Progress {
+        Progress {
+            passes_done: self.passes_done,
+            tiles_done: self.tiles_done.load(Ordering::Relaxed),
+            total_tiles: self.tiles.len() as u32,
+            samples_done: self.samples_done.load(Ordering::Relaxed),
+            total_samples: self.total_samples,
+            rays: self.rays.load(Ordering::Relaxed),
+            noise: self.noise,
+            elapsed: self.start.elapsed(),
+            time_limit: self.settings.time_limit,
+        }
+    }
+
+    fn out_of_time(&self) -> bool {
+        self.settings
+            .time_limit
+            .is_some_and(|limit| self.start.elapsed() >= limit)
+    }
+}
+
+impl Renderer {
+    /// A renderer that reports no progress and can't be cancelled.
+    pub fn new() -> Self {
+        Self {
+            progress: Box::new(SilentProgress),
+            cancellation: CancellationToken::new(),
+            preview: None,
+        }
+    }
+
+    pub fn with_progress(mut self, progress: impl ProgressSink + 'static) -> Self {
+        self.progress = Box::new(progress);
+        self
+    }
+
+    /// Cancelling `cancellation` makes [`Renderer::render`] stop early and return the image
+    /// averaged from the samples taken so far. Pixels without any are black.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Calls `preview` with the image so far after a pass, at most once per `interval`.
+    pub fn with_preview(
+        mut self,
+        interval: Duration,
+        preview: impl Fn(&Framebuffer) + Send + Sync + 'static,
+    ) -> Self {
+        self.preview = Some((interval, Box::new(preview)));
+        self
+    }
+
+    pub fn render(&self, scene: &Scene, settings: &RenderSettings) -> Result<Framebuffer> {
+        self.render_output(scene, settings)
+            .map(|output| output.framebuffer)
+    }
+
+    /// Renders like [`Renderer::render`], also returning the extra layers and how many samples
+    /// each pixel took.
+    pub fn render_output(&self, scene: &Scene, settings: &RenderSettings) -> Result<RenderOutput> {
+        if settings.samples_per_pixel == 0
+            && settings.time_limit.is_none()
+            && settings.noise_threshold.is_none()
+        {
+            return Err(anyhow!(
+                "a render without a sample count needs a time limit or a noise threshold"
+            ));
+        }
+        // Below half a pixel a sample could miss the pixel it was taken in.
+        if !(settings.filter.radius >= 0.5 && settings.filter.radius.is_finite()) {
+            return Err(anyhow!(
+                "the filter radius must be at least 0.5 pixels, not {}",
+                settings.filter.radius
+            ));
+        }
+
+        let full = Tile {
+            x: 0,
+            y: 0,
+            width: settings.width,
+            height: settings.height,
+        };
+        let region = match settings.crop {
+            Some(crop) => crop.resolve(settings.width, settings.height)?,
+            None => full,
+        };
+        // The framebuffer covers either the whole image or just the crop window.
+        let frame = if settings.crop_full_frame {
+            full
+        } else {
+            region
+        };
+        // Pixels just outside a crop window are sampled too, they splat into its edges.
+        let sampled = region.grow(settings.filter.margin(), &full);
+
+        // World
+        let world = BvhTree::new(scene.models.models.iter().map(|m| m.as_ref()).collect())?;
+        let lights: Vec<&dyn Hittable> = scene
+            .models
+            .lights
+            .iter()
+            .map(|&i| scene.models.models[i].as_ref())
+            .collect();
+
+        // Progress
+        let mut state = RenderState {
+            settings,
+            scene,
+            world,
+            lights,
+            material_ids: MaterialIds::new(&scene.models.models),
+            start: Instant::now(),
+            sampled,
+            tiles: tiles(&sampled, settings.tile_size, settings.tile_order),
+            passes_done: 0,
+            noise: None,
+            tiles_done: AtomicU32::new(0),
+            samples_done: AtomicU64::new(0),
+            rays: AtomicU64::new(0),
+            total_samples: sampled.pixels() * settings.samples_per_pixel as u64,
+        };
+
+        // Render
+        self.progress.message(&format!("🌲 {}", state.world));
+        self.progress.message(&format!("🎲 Seed {}", settings.seed));
+        self.progress.start(&state.progress());
+
+        let mut film = Film::new(sampled, &settings.aovs);
+        if settings.integrator != Aov::Beauty {
+            film = film.with_signed_values();
+        }
+        let film = Mutex::new(film);
+        let mut last_preview = Instant::now();
+        loop {
+            state.tiles_done.store(0, Ordering::Relaxed);
+            let samples_before = state.samples_done.load(Ordering::Relaxed);
+            let finished = self.render_pass(&state, &film);
+            // Every pixel has reached its sample count or converged.
+            if !finished || state.samples_done.load(Ordering::Relaxed) == samples_before {
+                break;
+            }
+            state.passes_done += 1;
+
+            let film = film.lock().unwrap();
+            state.noise = film.noise();
+            self.progress.update(&state.progress());
+            if let Some((interval, preview)) = &self.preview {
+                if last_preview.elapsed() >= *interval {
+                    preview(&film.to_framebuffer(&region, &frame));
+                    last_preview = Instant::now();
+                }
+            }
+            if let (Some(threshold), Some(noise)) = (settings.noise_threshold, state.noise) {
+                if noise <= threshold {
+                    break;
+                }
+            }
+        }
+
+        self.progress.finish(&state.progress());
+
+        let film = film.into_inner().unwrap();
+        Ok(RenderOutput {
+            framebuffer: film.to_framebuffer(&region, &frame),
+            sample_counts: film.sample_counts(&region, &frame),
+            aovs: settings
+                .aovs
+                .iter()
+                .enumerate()
+                .map(|(i, &aov)| (aov, film.aov_framebuffer(i, &region, &frame)))
+                .collect(),
+        })
+    }
+
+    // Adds a pass of samples to every pixel that still needs them, returns false if the pass was
+    // cut short.
+    fn render_pass(&self, state: &RenderState, film: &Mutex<Film>) -> bool {
+        // Threads take the tiles in order, so the first ones are done first.
+        let next_tile = AtomicUsize::new(0);
+        let interrupted = AtomicBool::new(false);
+        // Splats of neighbouring tiles overlap, they are added up once the pass is over.
+        let splats = Mutex::new(Vec::with_capacity(state.tiles.len()));
+        (0..rayon::current_num_threads())
+            .into_par_iter()
+            .for_each(|_| loop {
+                let Some(tile) = state.tiles.get(next_tile.fetch_add(1, Ordering::Relaxed)) else {
+                    return;
+                };
+                if self.cancellation.is_cancelled() || state.out_of_time() {
+                    interrupted.store(true, Ordering::Relaxed);
+                    return;
+                }
+
+                let tile_start = Instant::now();
+                take_ray_count();
+                let before = film.lock().unwrap().tile(tile);
+                let mut splat =
+                    SplatTile::new(tile.grow(state.settings.filter.margin(), &state.sampled));
+                let (pixels, aovs) = render_tile(tile, &before, &mut splat, state);
+                film.lock().unwrap().merge_tile(tile, &pixels, &aovs);
+                splats.lock().unwrap().push(splat);
+
+                let stats = TileStats {
+                    tile: *tile,
+                    samples: pixels.iter().map(|p| p.samples() as u64).sum(),
+                    rays: take_ray_count(),
+                    elapsed: tile_start.elapsed(),
+                };
+                self.progress.tile_finished(&stats);
+                state.rays.fetch_add(stats.rays, Ordering::Relaxed);
+                state
+                    .samples_done
+                    .fetch_add(stats.samples, Ordering::Relaxed);
+                state.tiles_done.fetch_add(1, Ordering::Relaxed);
+                self.progress.update(&state.progress());
+            });
+
+        film.lock()
+            .unwrap()
+            .merge_splats(splats.into_inner().unwrap());
+        !interrupted.into_inner()
+    }
+}
+
+// New samples for the pixels of `tile`, continuing from their statistics so far in `before`.
+// The main image is filtered into `splats`, the sums of the extra layers are returned per pixel.
+fn render_tile(
+    tile: &Tile,
+    before: &[PixelStats],
+    splats: &mut SplatTile,
+    state: &RenderState,
+) -> (Vec<PixelStats>, Vec<Color>) {
+    let settings = state.settings;
+    let RenderSettings {
+        width: img_width,
+        height: img_height,
+        max_depth,
+        roulette_depth,
+        seed,
+        ..
+    } = *settings;
+    // Stratification is planned for the full sample count, or a pass of an open-ended render.
+    let planned_samples = match settings.samples_per_pixel {
+        0 => settings.samples_per_pass,
+        spp => spp,
+    };
+    let mut sampler = settings.sampler.build(seed, planned_samples);
+
+    // The main image first, then the extra layers.
+    let layers: Vec<Aov> = [settings.integrator]
+        .into_iter()
+        .chain(settings.aovs.iter().copied())
+        .collect();
+    let path_traced = layers.contains(&Aov::Beauty);
+    let first_hit_layers = layers.iter().any(|&aov| aov != Aov::Beauty);
+    let mut values = vec![Color::zeros(); layers.len()];
+
+    let pixels = (tile.y..tile.y + tile.height)
+        .flat_map(|row| (tile.x..tile.x + tile.width).map(move |x| (x, row)));
+    let mut stats = Vec::with_capacity(before.len());
+    let mut aovs = vec![Color::zeros(); before.len() * settings.aovs.len()];
+    for (i, ((x, row), before)) in pixels.zip(before).enumerate() {
+        // The camera's `v` goes up the image.
+        let y = img_height - 1 - row;
+        let first_sample = before.samples();
+        let mut pixel_stats = PixelStats::default();
+        for sample in first_sample..first_sample + settings.pass_samples(before) {
+            sampler.start_sample(x, y, sample);
+            let offset = sampler.get_2d();
+            let u = (x as f32 + offset.x) / (img_width - 1) as f32;
+            let v = (y as f32 + offset.y) / (img_height - 1) as f32;
+
+            let lens = sampler.get_2d();
+            let ray = state.scene.camera.get_ray(u, v, lens, sampler.get_1d());
+            if first_hit_layers {
+                first_hit(
+                    &ray,
+                    &state.world,
+                    &state.scene.camera,
+                    &state.material_ids,
+                    &layers,
+                    &mut values,
+                );
+            }
+            if path_traced {
+                let color = radiance(
+                    &ray,
+                    &state.world,
+                    &state.lights,
+                    &state.scene.background,
+                    max_depth,
+                    roulette_depth,
+                    &mut sampler,
+                );
+                for (layer, value) in layers.iter().zip(values.iter_mut()) {
+                    if *layer == Aov::Beauty {
+                        *value = color;
+                    }
+                }
+            }
+
+            pixel_stats.add(values[0]);
+            if settings.integrator.is_averaged() {
+                // `offset.y` goes up within the pixel, film rows go down.
+                splats.add(
+                    &settings.filter,
+                    x as f32 + offset.x,
+                    row as f32 + 1.0 - offset.y,
+                    values[0],
+                );
+            } else if sample == 0 {
+                splats.set(x, row, values[0]);
+            }
+            let sums = &mut aovs[i * settings.aovs.len()..(i + 1) * settings.aovs.len()];
+            for ((sum, value), aov) in sums.iter_mut().zip(&values[1..]).zip(&settings.aovs) {
+                if aov.is_averaged() {
+                    *sum += value;
+                } else if sample == 0 {
+                    *sum = *value;
+                }
+            }
+        }
+        stats.push(pixel_stats);
+    }
+    (stats, aovs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        background::Background, camera::Camera, hittable::sphere::Sphere,
+        materials::lambertian::Lambertian, vec::Vec3,
+    };
+
+    // A grey sphere against a solid sky, so every pixel a sample reaches has some light.
+    fn scene(settings: &RenderSettings) -> Scene {
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 4.0),
+            Vec3::zeros(),
+            Vec3::y(),
+            40.0,
+            settings.aspect_ratio(),
+            0.0,
+            4.0,
+        );
+        Scene::builder(camera)
+            .with_background(Background::Solid(Color::new(0.5, 0.7, 1.0)))
+            .with_object(Sphere::new(
+                Vec3::zeros(),
+                1.0,
+                Lambertian::new(Color::repeat(0.5)),
+            ))
+            .build()
+    }
+
+    // Renders on a single thread, so tiles are done one after the other in order.
+    fn render(renderer: &Renderer, settings: &RenderSettings) -> Result<RenderOutput> {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(|| renderer.render_output(&scene(settings), settings))
+    }
+
+    // Cancels the render once it has finished a tile.
+    struct CancelAfterTile(CancellationToken);
+
+    impl ProgressSink for CancelAfterTile {
+        fn update(&self, _progress: &Progress) {}
+
+        fn tile_finished(&self, _stats: &TileStats) {
+            self.0.cancel();
+        }
+    }
+
+    // Keeps the progress a render finished with.
+    #[derive(Clone, Default)]
+    struct LastProgress(Arc<Mutex<Option<Progress>>>);
+
+    impl ProgressSink for LastProgress {
+        fn update(&self, _progress: &Progress) {}
+
+        fn finish(&self, progress: &Progress) {
+            *self.0.lock().unwrap() = Some(*progress);
+        }
+    }
+
+    fn stats(values: impl IntoIterator<Item = f32>) -> PixelStats {
+        let mut stats = PixelStats::default();
+        for value in values {
+            stats.add(Color::repeat(value));
+        }
+        stats
+    }
+
+    #[test]
+    fn adaptive_sampling_waits_for_the_minimum_samples() {
+        let adaptive = AdaptiveSampling::default();
+        assert!(!adaptive.converged(&stats([0.5; 15])));
+        assert!(adaptive.converged(&stats([0.5; 16])));
+    }
+
+    #[test]
+    fn adaptive_sampling_stops_once_the_confidence_interval_is_narrow_enough() {
+        let adaptive = AdaptiveSampling {
+            min_samples: 2,
+            threshold: 0.05,
+        };
+        // Alternating 0 and 2 has a mean of 1 and a standard deviation of about 1, so the
+        // interval is about 1.96 / sqrt(n) wide relative to the mean, under 0.05 from about 1500
+        // samples.
+        let alternating = |n| stats((0..n).map(|i| (i % 2) as f32 * 2.0));
+        assert!(!adaptive.converged(&alternating(1000)));
+        assert!(adaptive.converged(&alternating(1600)));
+    }
+
+    #[test]
+    fn converged_pixels_take_no_more_samples() {
+        let settings = RenderSettings {
+            samples_per_pixel: 64,
+            samples_per_pass: 4,
+            adaptive: Some(AdaptiveSampling::default()),
+            ..RenderSettings::default()
+        };
+        assert_eq!(settings.pass_samples(&stats([0.5; 8])), 4);
+        assert_eq!(settings.pass_samples(&stats([0.5; 16])), 0);
+        assert_eq!(settings.pass_samples(&stats((0..62).map(|i| i as f32))), 2);
+    }
+
+    #[test]
+    fn cancelling_returns_the_tiles_done_so_far() {
+        let settings = RenderSettings {
+            width: 64,
+            height: 32,
+            samples_per_pixel: 8,
+            max_depth: 4,
+            tile_size: 16,
+            tile_order: TileOrder::Scanline,
+            ..RenderSettings::default()
+        };
+        let cancellation = CancellationToken::new();
+        let renderer = Renderer::new()
+            .with_progress(CancelAfterTile(cancellation.clone()))
+            .with_cancellation(cancellation.clone());
+        let output = render(&renderer, &settings).unwrap();
+        assert!(cancellation.is_cancelled());
+
+        let framebuffer = &output.framebuffer;
+        assert_eq!((framebuffer.width(), framebuffer.height()), (64, 32));
+        for y in 0..32 {
+            for x in 0..64 {
+                let color = framebuffer.get(x, y);
+                let samples = output.sample_counts.get(x, y);
+                if x < 16 && y < 16 {
+                    // One pass over the first tile.
+                    assert!(color.min() > 0.0, "{} {}: {:?}", x, y, color);
+                    assert_eq!(samples, settings.samples_per_pass, "{} {}", x, y);
+                } else {
+                    assert_eq!(color, Color::zeros(), "{} {}", x, y);
+                    assert_eq!(samples, 0, "{} {}", x, y);
+                }
+            }
+        }
+    }
+
+    // Small and shallow, the stopping rules don't depend on the image.
+    fn stopping_settings() -> RenderSettings {
+        RenderSettings {
+            width: 32,
+            height: 16,
+            max_depth: 4,
+            tile_size: 8,
+            samples_per_pass: 4,
+            ..RenderSettings::default()
+        }
+    }
+
+    // Renders and returns the output with the progress it finished with.
+    fn render_until_stopped(settings: &RenderSettings) -> (RenderOutput, Progress) {
+        let last = LastProgress::default();
+        let renderer = Renderer::new().with_progress(last.clone());
+        let output = render(&renderer, settings).unwrap();
+        let progress = last.0.lock().unwrap().unwrap();
+        (output, progress)
+    }
+
+    #[test]
+    fn stops_at_the_sample_count() {
+        for (samples_per_pixel, passes) in [(4, 1), (10, 3)] {
+            let settings = RenderSettings {
+                samples_per_pixel,
+                ..stopping_settings()
+            };
+            let (output, progress) = render_until_stopped(&settings);
+            assert_eq!(progress.passes_done, passes);
+            assert_eq!(progress.samples_done, 32 * 16 * samples_per_pixel as u64);
+            assert!(output
+                .sample_counts
+                .counts()
+                .iter()
+                .all(|&n| n == samples_per_pixel));
+        }
+    }
+
+    #[test]
+    fn stops_after_the_first_pass_below_the_noise_threshold() {
+        let settings = RenderSettings {
+            samples_per_pixel: 0,
+            noise_threshold: Some(1e6),
+            ..stopping_settings()
+        };
+        let (output, progress) = render_until_stopped(&settings);
+        assert_eq!(progress.passes_done, 1);
+        assert!(progress.noise.is_some_and(|noise| noise <= 1e6));
+        assert!(output.sample_counts.counts().iter().all(|&n| n == 4));
+    }
+
+    #[test]
+    fn a_zero_time_limit_still_returns_an_image() {
+        let settings = RenderSettings {
+            samples_per_pixel: 0,
+            time_limit: Some(Duration::ZERO),
+            ..stopping_settings()
+        };
+        let (output, progress) = render_until_stopped(&settings);
+        assert_eq!(progress.passes_done, 0);
+        let framebuffer = &output.framebuffer;
+        assert_eq!((framebuffer.width(), framebuffer.height()), (32, 16));
+        // Pixels without samples are black, not NaN.
+        assert!(framebuffer.pixels().iter().all(|c| *c == Color::zeros()));
+        assert_eq!(output.sample_counts.max(), 0);
+    }
+}
@@ -0,0 +1,33 @@
+use rand::{rngs::SmallRng, SeedableRng};
+
+// SplitMix64 finalizer, spreads nearby inputs such as neighbouring pixels over the whole range.
+#[inline(always)]
+pub fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Random stream `stream` of a render seeded with `seed`.
+///
+/// The stream only depends on its inputs, never on which thread asks for it or when.
+#[inline(always)]
+pub fn stream_rng(seed: u64, stream: u64) -> SmallRng {
+    SmallRng::seed_from_u64(mix(seed ^ mix(stream)))
+}
+
+/// Random stream of sample `sample` of pixel (`x`, `y`), so a pixel gets the same samples no
+/// matter how they're split into passes.
+#[inline(always)]
+pub fn sample_rng(seed: u64, x: u32, y: u32, sample: u32) -> SmallRng {
+    let pixel = mix(seed ^ mix((y as u64) << 32 | x as u64));
+    SmallRng::seed_from_u64(mix(pixel ^ sample as u64))
+}
+
+// FNV-1a, stable across runs and platforms unlike the standard library's hasher.
+pub fn name_stream(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
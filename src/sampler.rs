@@ -0,0 +1,161 @@
+//! Sample points for the dimensions of a path: pixel position, lens position, then a fixed set
+//! per bounce.
+//!
+//! Samplers are restarted for every pixel sample and hand out the dimensions in the order they
+//! are asked for, so every sample of a pixel sees the same dimension for the same decision.
+//! Their values only depend on the seed, the pixel, the sample index and the dimension, which
+//! keeps renders independent of tiling and threads.
+
+use clap::ValueEnum;
+
+use crate::vec::Vec2;
+
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+pub mod warp;
+
+use self::{
+    halton::HaltonSampler, independent::IndependentSampler, sobol::SobolSampler,
+    stratified::StratifiedSampler,
+};
+
+// Largest f32 below one, samples are in `[0, 1)`.
+pub(crate) const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+pub trait Sampler {
+    /// Restarts at the first dimension of sample `sample` of pixel (`x`, `y`).
+    fn start_sample(&mut self, x: u32, y: u32, sample: u32);
+    fn get_1d(&mut self) -> f32;
+    fn get_2d(&mut self) -> Vec2;
+}
+
+impl<S: Sampler + ?Sized> Sampler for Box<S> {
+    #[inline(always)]
+    fn start_sample(&mut self, x: u32, y: u32, sample: u32) {
+        self.as_mut().start_sample(x, y, sample)
+    }
+
+    #[inline(always)]
+    fn get_1d(&mut self) -> f32 {
+        self.as_mut().get_1d()
+    }
+
+    #[inline(always)]
+    fn get_2d(&mut self) -> Vec2 {
+        self.as_mut().get_2d()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SamplerKind {
+    /// Uniform random values, as if there were no sampler.
+    Independent,
+    /// One jittered sample per stratum of the samples per pixel, shuffled between dimensions.
+    Stratified,
+    /// The Halton sequence with Owen scrambling, random beyond its first dimensions.
+    Halton,
+    /// The first two dimensions of the Sobol sequence, Owen scrambled and shuffled per dimension.
+    #[default]
+    Sobol,
+}
+
+impl SamplerKind {
+    /// A sampler for renders seeded with `seed`. Stratification is planned for
+    /// `samples_per_pixel` samples, later ones start over with new jitter.
+    pub fn build(&self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => {
+                Box::new(StratifiedSampler::new(seed, samples_per_pixel.max(1)))
+            }
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+/// Element `i` of a random permutation of `0..len` chosen by `seed`, without storing it.
+///
+/// Kensler's hashed permutation from "Correlated Multi-Jittered Sampling".
+pub(crate) fn permutation_element(mut i: u32, len: u32, seed: u32) -> u32 {
+    let p = seed;
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            return (i.wrapping_add(p)) % len;
+        }
+    }
+}
+
+/// Maps the bits of `bits` to `[0, 1)`.
+#[inline(always)]
+pub(crate) fn bits_to_unit(bits: u32) -> f32 {
+    (bits >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_lie_in_the_unit_interval() {
+        for kind in SamplerKind::value_variants() {
+            let mut sampler = kind.build(3, 16);
+            for sample in 0..64 {
+                sampler.start_sample(sample % 5, sample / 5, sample);
+                for _ in 0..80 {
+                    let p = sampler.get_2d();
+                    let x = sampler.get_1d();
+                    for value in [p.x, p.y, x] {
+                        assert!((0.0..1.0).contains(&value), "{:?} gave {}", kind, value);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unit_conversion_stays_below_one() {
+        assert_eq!(bits_to_unit(0), 0.0);
+        assert!(bits_to_unit(u32::MAX) < 1.0);
+    }
+
+    #[test]
+    fn permutations_visit_every_element_once() {
+        for len in [1, 2, 5, 64, 100] {
+            for seed in [0, 1, 0x1234_5678] {
+                let mut seen = vec![false; len as usize];
+                for i in 0..len {
+                    let element = permutation_element(i, len, seed) as usize;
+                    assert!(!seen[element]);
+                    seen[element] = true;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    rng::{mix, sample_rng},
+    vec::Vec2,
+};
+
+use super::{permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+// Bases of the Halton dimensions, later dimensions are too correlated to be worth it.
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// The Halton sequence with a separate Owen scrambling per pixel. Dimensions past the supported
+/// bases are independent random values.
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: u64,
+    sample: u32,
+    dimension: usize,
+    rng: SmallRng,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: 0,
+            sample: 0,
+            dimension: 0,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    fn next(&mut self) -> f32 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        match PRIMES.get(dimension) {
+            Some(&base) => owen_scrambled_radical_inverse(
+                base,
+                self.sample,
+                mix(self.pixel ^ mix(dimension as u64)),
+            ),
+            None => self.rng.gen(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, x: u32, y: u32, sample: u32) {
+        self.pixel = mix(self.seed ^ mix((y as u64) << 32 | x as u64));
+        self.sample = sample;
+        self.dimension = 0;
+        self.rng = sample_rng(self.seed, x, y, sample);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        self.next()
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        Vec2::new(self.next(), self.next())
+    }
+}
+
+// The digits of `index` in `base` mirrored around the point, each digit permuted depending on the
+// digits before it.
+fn owen_scrambled_radical_inverse(base: u32, index: u32, hash: u64) -> f32 {
+    radical_inverse(base, index, |digit, reversed_digits| {
+        permutation_element(digit, base, mix(hash ^ reversed_digits) as u32)
+    })
+}
+
+// The digits of `index` in `base` mirrored around the point, passing each digit through `permute`
+// along with the digits already mirrored.
+#[inline(always)]
+fn radical_inverse(base: u32, mut index: u32, permute: impl Fn(u32, u64) -> u32) -> f32 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits: u64 = 0;
+    // Stop once further digits can't change an f32.
+    while 1.0 - (base - 1) as f64 * inv_base_m < 1.0 - f32::EPSILON as f64 / 4.0 {
+        let next = index / base;
+        let digit = permute(index - next * base, reversed_digits);
+        reversed_digits = reversed_digits * base as u64 + digit as u64;
+        inv_base_m *= inv_base;
+        index = next;
+    }
+    ((inv_base_m * reversed_digits as f64) as f32).min(ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unscrambled_points_match_the_halton_sequence() {
+        let halton = |base, index| radical_inverse(base, index, |digit, _| digit);
+        let base_2 = [0.0, 0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875];
+        let base_3 = [0.0, 3.0, 6.0, 1.0, 4.0, 7.0, 2.0, 5.0, 8.0].map(|n| n / 9.0);
+        for (i, &expected) in base_2.iter().enumerate() {
+            assert_eq!(halton(2, i as u32), expected);
+        }
+        for (i, &expected) in base_3.iter().enumerate() {
+            assert!((halton(3, i as u32) - expected).abs() < 1e-6, "point {}", i);
+        }
+    }
+
+    #[test]
+    fn scrambling_keeps_one_point_per_interval() {
+        for (base, count) in [(2, 64), (3, 27), (5, 25)] {
+            for hash in [0, 1, 0xdead_beef] {
+                let mut intervals = vec![0; count];
+                for i in 0..count as u32 {
+                    let x = owen_scrambled_radical_inverse(base, i, hash);
+                    intervals[(x * count as f32) as usize] += 1;
+                }
+                assert!(intervals.iter().all(|&n| n == 1), "base {}", base);
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{rng::sample_rng, vec::Vec2};
+
+use super::Sampler;
+
+pub struct IndependentSampler {
+    seed: u64,
+    rng: SmallRng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    #[inline(always)]
+    fn start_sample(&mut self, x: u32, y: u32, sample: u32) {
+        self.rng = sample_rng(self.seed, x, y, sample);
+    }
+
+    #[inline(always)]
+    fn get_1d(&mut self) -> f32 {
+        self.rng.gen()
+    }
+
+    #[inline(always)]
+    fn get_2d(&mut self) -> Vec2 {
+        Vec2::new(self.rng.gen(), self.rng.gen())
+    }
+}
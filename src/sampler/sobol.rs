@@ -0,0 +1,133 @@
+use crate::{rng::mix, vec::Vec2};
+
+use super::{bits_to_unit, Sampler};
+
+/// Burley's shuffled, Owen-scrambled Sobol sampler from "Practical Hash-based Owen Scrambling".
+///
+/// Every dimension, or pair of dimensions, draws from the first two Sobol dimensions, which are
+/// well stratified together, with its own scrambling and its own shuffle of the sample order so
+/// dimensions aren't correlated. Works for any number of samples, and every power of two prefix
+/// is well distributed.
+pub struct SobolSampler {
+    seed: u64,
+    pixel: u64,
+    sample: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel: 0,
+            sample: 0,
+            dimension: 0,
+        }
+    }
+
+    // Scrambled Sobol point of the current sample in the next dimension.
+    fn next(&mut self) -> (u32, u32) {
+        let hash = mix(self.pixel ^ mix(self.dimension as u64));
+        self.dimension += 1;
+
+        let index = nested_uniform_scramble(self.sample, hash as u32);
+        let (x, y) = sobol_2d(index);
+        (
+            nested_uniform_scramble(x, mix(hash ^ 1) as u32),
+            nested_uniform_scramble(y, mix(hash ^ 2) as u32),
+        )
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, x: u32, y: u32, sample: u32) {
+        self.pixel = mix(self.seed ^ mix((y as u64) << 32 | x as u64));
+        self.sample = sample;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        bits_to_unit(self.next().0)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let (x, y) = self.next();
+        Vec2::new(bits_to_unit(x), bits_to_unit(y))
+    }
+}
+
+// Dimensions 0 and 1 of the Sobol sequence as fixed point fractions.
+#[inline(always)]
+fn sobol_2d(mut index: u32) -> (u32, u32) {
+    let x = index.reverse_bits();
+    // The direction numbers of the second dimension follow from x + 1.
+    let mut y = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            y ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    (x, y)
+}
+
+// Flips every bit depending on the bits above it, which Laine and Karras' hash does for the bits
+// below it, hence the reversals.
+#[inline(always)]
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+#[inline(always)]
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unscrambled_points_match_the_sobol_sequence() {
+        let x = [0.0, 0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875];
+        let y = [0.0, 0.5, 0.75, 0.25, 0.625, 0.125, 0.375, 0.875];
+        for i in 0..8 {
+            let (sx, sy) = sobol_2d(i as u32);
+            assert_eq!(
+                (bits_to_unit(sx), bits_to_unit(sy)),
+                (x[i], y[i]),
+                "point {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn scrambled_points_keep_one_point_per_elementary_interval() {
+        let mut sampler = SobolSampler::new(7);
+        for dimension in 0..4 {
+            // 64 points fill each of the 1x64, 2x32, ... 64x1 grids of intervals once.
+            for columns in [1, 2, 4, 8, 16, 32, 64] {
+                let mut cells = vec![0; 64];
+                for sample in 0..64 {
+                    sampler.start_sample(3, 5, sample);
+                    for _ in 0..dimension {
+                        sampler.get_2d();
+                    }
+                    let p = sampler.get_2d();
+                    let column = (p.x * columns as f32) as usize;
+                    let row = (p.y * (64 / columns) as f32) as usize;
+                    cells[row * columns + column] += 1;
+                }
+                assert!(cells.iter().all(|&n| n == 1), "{} columns", columns);
+            }
+        }
+    }
+}
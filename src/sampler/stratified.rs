@@ -0,0 +1,118 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    rng::{mix, sample_rng},
+    vec::Vec2,
+};
+
+use super::{permutation_element, Sampler, ONE_MINUS_EPSILON};
+
+/// Splits every dimension into as many strata as there are samples per pixel, a grid of them for
+/// 2D dimensions, and gives each sample its own stratum.
+///
+/// Each dimension visits the strata in a different order, so dimensions aren't correlated.
+pub struct StratifiedSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    // Columns of the 2D grid, its rows are enough to hold `samples_per_pixel` cells.
+    columns: u32,
+    rows: u32,
+    pixel: u64,
+    sample: u32,
+    dimension: u32,
+    rng: SmallRng,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        let columns = (samples_per_pixel as f32).sqrt().ceil() as u32;
+        Self {
+            seed,
+            samples_per_pixel,
+            columns,
+            rows: samples_per_pixel.div_ceil(columns),
+            pixel: 0,
+            sample: 0,
+            dimension: 0,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    // The stratum of the current sample out of `strata` in the next dimension.
+    fn next_stratum(&mut self, strata: u32) -> u32 {
+        let seed = mix(self.pixel ^ mix(self.dimension as u64)) as u32;
+        self.dimension += 1;
+        permutation_element(self.sample % strata, strata, seed)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, x: u32, y: u32, sample: u32) {
+        self.pixel = mix(self.seed ^ mix((y as u64) << 32 | x as u64));
+        self.sample = sample;
+        self.dimension = 0;
+        self.rng = sample_rng(self.seed, x, y, sample);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let stratum = self.next_stratum(self.samples_per_pixel);
+        ((stratum as f32 + self.rng.gen::<f32>()) / self.samples_per_pixel as f32)
+            .min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> Vec2 {
+        let stratum = self.next_stratum(self.columns * self.rows);
+        let (column, row) = (stratum % self.columns, stratum / self.columns);
+        Vec2::new(
+            ((column as f32 + self.rng.gen::<f32>()) / self.columns as f32).min(ONE_MINUS_EPSILON),
+            ((row as f32 + self.rng.gen::<f32>()) / self.rows as f32).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts the samples of a pixel in each stratum of its second dimension, a 1D one if `columns`
+    // is `None` and a grid with `columns` columns otherwise.
+    fn strata_counts(samples_per_pixel: u32, columns: Option<u32>) -> Vec<u32> {
+        let mut sampler = StratifiedSampler::new(11, samples_per_pixel);
+        let strata = columns.map_or(samples_per_pixel, |c| c * sampler.rows);
+        let mut counts = vec![0; strata as usize];
+        for sample in 0..samples_per_pixel {
+            sampler.start_sample(2, 9, sample);
+            sampler.get_2d();
+            let stratum = match columns {
+                None => (sampler.get_1d() * strata as f32) as u32,
+                Some(columns) => {
+                    let p = sampler.get_2d();
+                    let row = (p.y * sampler.rows as f32) as u32;
+                    row * columns + (p.x * columns as f32) as u32
+                }
+            };
+            counts[stratum as usize] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn puts_one_sample_in_every_stratum() {
+        for samples_per_pixel in [1, 7, 10, 64] {
+            assert!(strata_counts(samples_per_pixel, None)
+                .iter()
+                .all(|&n| n == 1));
+        }
+        assert!(strata_counts(16, Some(4)).iter().all(|&n| n == 1));
+        assert!(strata_counts(49, Some(7)).iter().all(|&n| n == 1));
+    }
+
+    #[test]
+    fn leaves_spare_2d_strata_empty_for_non_square_counts() {
+        // 10 samples on a 4x3 grid.
+        let counts = strata_counts(10, Some(4));
+        assert_eq!(counts.len(), 12);
+        assert!(counts.iter().all(|&n| n <= 1));
+        assert_eq!(counts.iter().sum::<u32>(), 10);
+    }
+}
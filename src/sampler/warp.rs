@@ -0,0 +1,48 @@
+//! Maps uniform samples in `[0, 1)²` onto other domains while keeping them well distributed,
+//! unlike rejection sampling which throws away the stratification of a sampler.
+
+use std::f32::consts::{FRAC_PI_4, PI};
+
+use crate::vec::{Vec2, Vec3};
+
+/// Uniform on the unit disk, with Shirley's concentric mapping.
+#[inline(always)]
+pub fn concentric_disk(u: Vec2) -> Vec2 {
+    let offset = 2.0 * u - Vec2::new(1.0, 1.0);
+    if offset.x == 0.0 && offset.y == 0.0 {
+        return Vec2::zeros();
+    }
+
+    let (r, theta) = if offset.x.abs() > offset.y.abs() {
+        (offset.x, FRAC_PI_4 * (offset.y / offset.x))
+    } else {
+        (
+            offset.y,
+            2.0 * FRAC_PI_4 - FRAC_PI_4 * (offset.x / offset.y),
+        )
+    };
+    r * Vec2::new(theta.cos(), theta.sin())
+}
+
+/// Cosine weighted on the hemisphere around `+z`, with density `z / π`.
+#[inline(always)]
+pub fn cosine_hemisphere(u: Vec2) -> Vec3 {
+    let d = concentric_disk(u);
+    let z = (1.0 - d.magnitude_squared()).max(0.0).sqrt();
+    Vec3::new(d.x, d.y, z)
+}
+
+/// Uniform on the unit sphere.
+#[inline(always)]
+pub fn uniform_sphere(u: Vec2) -> Vec3 {
+    let z = 1.0 - 2.0 * u.x;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.y;
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Uniform in the unit ball, `uc` picks the radius.
+#[inline(always)]
+pub fn uniform_ball(uc: f32, u: Vec2) -> Vec3 {
+    uc.cbrt() * uniform_sphere(u)
+}
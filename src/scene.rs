@@ -1,26 +1,117 @@
+pub mod file;
+pub mod obj;
+
 use rand::Rng;
 
 use crate::{
+    background::Background,
+    camera::Camera,
     hittable::Hittable,
     materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal},
+    rng::{name_stream, stream_rng},
     vec::{random_vec, Vec3},
     Color, Sphere,
 };
 
 #[derive(Default)]
-pub struct ModelList {
-    pub models: Vec<Box<dyn Hittable>>,
+pub(crate) struct ModelList {
+    pub(crate) models: Vec<Box<dyn Hittable>>,
+    // Indices into `models` of the emissive ones, sampled directly while rendering.
+    pub(crate) lights: Vec<usize>,
+}
+
+/// The objects to render, the camera looking at them and the background behind them.
+pub struct Scene {
+    pub(crate) models: ModelList,
+    pub(crate) camera: Camera,
+    pub(crate) background: Background,
+}
+
+/// Collects objects into a [`Scene`].
+///
+/// ```
+/// use ray_tracing_one_weekend::{
+///     hittable::sphere::Sphere, materials::lambertian::Lambertian, vec::Vec3, Camera, Scene,
+/// };
+///
+/// let camera = Camera::new(
+///     Vec3::new(0.0, 0.0, 5.0),
+///     Vec3::zeros(),
+///     Vec3::new(0.0, 1.0, 0.0),
+///     40.0,
+///     2.0,
+///     0.0,
+///     5.0,
+/// );
+/// let scene = Scene::builder(camera)
+///     .with_object(Sphere::new(Vec3::zeros(), 1.0, Lambertian::new(Vec3::new(0.8, 0.3, 0.3))))
+///     .build();
+/// ```
+pub struct SceneBuilder {
+    models: ModelList,
+    camera: Camera,
+    background: Background,
+}
+
+impl Scene {
+    pub fn builder(camera: Camera) -> SceneBuilder {
+        SceneBuilder {
+            models: ModelList::default(),
+            camera,
+            background: Background::default(),
+        }
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+}
+
+impl SceneBuilder {
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn with_object(mut self, hittable: impl Hittable + 'static) -> Self {
+        self.models.push(hittable);
+        self
+    }
+
+    /// Adds an emissive object that is also sampled directly, which makes small lights converge
+    /// much faster. Its `pdf_value` and `random_direction` must be implemented.
+    pub fn with_light(mut self, hittable: impl Hittable + 'static) -> Self {
+        self.models.push_light(hittable);
+        self
+    }
+
+    pub fn build(self) -> Scene {
+        Scene {
+            models: self.models,
+            camera: self.camera,
+            background: self.background,
+        }
+    }
 }
 
 impl ModelList {
     fn push(&mut self, hittable: impl Hittable + 'static) {
         self.models.push(Box::new(hittable));
     }
+
+    fn push_light(&mut self, hittable: impl Hittable + 'static) {
+        self.lights.push(self.models.len());
+        self.push(hittable);
+    }
 }
 
-pub fn random_scene_models() -> ModelList {
+pub(crate) fn random_scene_models(seed: u64) -> ModelList {
     let mut world = ModelList::default();
-    let mut rng = rand::thread_rng();
+    let mut rng = stream_rng(seed, name_stream("random_scene"));
 
     let ground_mat = Lambertian::new(Color::new(0.5, 0.5, 0.5));
     world.push(Sphere::new(
@@ -65,3 +156,26 @@ pub fn random_scene_models() -> ModelList {
 
     world
 }
+
+/// The cover of the book, with small spheres placed from `seed`.
+pub fn random_scene(aspect_ratio: f32, seed: u64) -> Scene {
+    let look_from = Vec3::new(13.0, 2.0, 3.0);
+    let look_at = Vec3::new(0.0, 0.0, 0.0);
+    let focus_dist = (look_from - look_at).magnitude();
+    let aperture = 0.1;
+    let camera = Camera::new(
+        look_from,
+        look_at,
+        Vec3::new(0.0, 1.0, 0.0),
+        20.0,
+        aspect_ratio,
+        aperture,
+        focus_dist,
+    );
+
+    Scene {
+        models: random_scene_models(seed),
+        camera,
+        background: Background::default(),
+    }
+}
@@ -0,0 +1,955 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use toml::Spanned;
+
+use crate::{
+    background::Background,
+    camera::Camera,
+    hittable::{
+        bvh::Bvh,
+        cuboid::Cuboid,
+        disk::Disk,
+        instance::Instance,
+        moving_sphere::MovingSphere,
+        plane::Plane,
+        quad::Quad,
+        sphere::Sphere,
+        triangle::{MeshLight, Triangle},
+        Hittable,
+    },
+    materials::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+        Material,
+    },
+    rng::{name_stream, stream_rng},
+    textures::{
+        checker::CheckerTexture,
+        image::{Filtering, ImageTexture, WrapMode},
+        noise::{NoiseKind, NoiseTexture},
+        solid_color::SolidColor,
+        Texture,
+    },
+    transform::Transform,
+    vec::{Color, Vec3},
+};
+
+use super::{obj::load_obj, ModelList, Scene};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    camera: CameraDesc,
+    background: Option<BackgroundDesc>,
+    #[serde(default)]
+    textures: HashMap<String, Spanned<TextureDesc>>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialDesc>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectEntry>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: [f32; 3],
+    look_at: [f32; 3],
+    #[serde(default = "default_view_up")]
+    view_up: [f32; 3],
+    vertical_fov: f32,
+    #[serde(default)]
+    aperture: f32,
+    focus_dist: Option<f32>,
+    #[serde(default = "default_shutter")]
+    shutter: [f32; 2],
+}
+
+fn default_view_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_shutter() -> [f32; 2] {
+    [0.0, 1.0]
+}
+
+fn default_time1() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDesc {
+    None,
+    Solid { color: [f32; 3] },
+    Gradient { bottom: [f32; 3], top: [f32; 3] },
+}
+
+// Either an inline colour or the name of a texture.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorSource {
+    Color([f32; 3]),
+    Texture(String),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid {
+        color: [f32; 3],
+    },
+    Checker {
+        scale: f32,
+        even: ColorSource,
+        odd: ColorSource,
+    },
+    Image {
+        path: PathBuf,
+        #[serde(default)]
+        wrap: WrapModeDesc,
+        #[serde(default)]
+        filtering: FilteringDesc,
+    },
+    Noise {
+        scale: f32,
+        #[serde(default)]
+        kind: NoiseKindDesc,
+        #[serde(default = "default_noise_color")]
+        color: [f32; 3],
+    },
+}
+
+fn default_noise_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum WrapModeDesc {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum FilteringDesc {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum NoiseKindDesc {
+    #[default]
+    Noise,
+    Turbulence,
+    Marble,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian { albedo: ColorSource },
+    Metal { albedo: ColorSource, fuzzy: f32 },
+    Dielectric { index_of_refraction: f32 },
+    DiffuseLight { emit: ColorSource },
+}
+
+// Any object can be placed with a transform, which makes it an instance of the untransformed one.
+#[derive(Deserialize)]
+struct ObjectEntry {
+    #[serde(flatten)]
+    shape: ObjectDesc,
+    transform: Option<TransformDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDesc {
+    #[serde(default)]
+    scale: ScaleDesc,
+    // Degrees about the x, y and z axes.
+    #[serde(default)]
+    rotate: [f32; 3],
+    #[serde(default)]
+    translate: [f32; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDesc {
+    Uniform(f32),
+    PerAxis([f32; 3]),
+}
+
+impl ScaleDesc {
+    fn is_uniform(&self) -> bool {
+        match *self {
+            ScaleDesc::Uniform(_) => true,
+            ScaleDesc::PerAxis([x, y, z]) => x == y && y == z,
+        }
+    }
+}
+
+impl Default for ScaleDesc {
+    fn default() -> Self {
+        ScaleDesc::Uniform(1.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    MovingSphere {
+        center0: [f32; 3],
+        center1: [f32; 3],
+        #[serde(default)]
+        time0: f32,
+        #[serde(default = "default_time1")]
+        time1: f32,
+        radius: f32,
+        material: String,
+    },
+    Triangle {
+        vertices: [[f32; 3]; 3],
+        material: String,
+    },
+    Quad {
+        corner: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+    },
+    Disk {
+        center: [f32; 3],
+        normal: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    Box {
+        min: [f32; 3],
+        max: [f32; 3],
+        material: String,
+    },
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+        material: String,
+    },
+    Mesh {
+        path: PathBuf,
+        material: Option<String>,
+    },
+}
+
+pub fn load(path: &Path, aspect_ratio: f32, seed: u64) -> Result<Scene> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to read scene file `{}`", path.display()))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse(&source, base_dir, aspect_ratio, seed)
+        .with_context(|| format!("failed to load scene file `{}`", path.display()))
+}
+
+pub fn parse(source: &str, base_dir: &Path, aspect_ratio: f32, seed: u64) -> Result<Scene> {
+    let desc: SceneDesc = toml::from_str(source)?;
+
+    let camera = build_camera(&desc.camera, aspect_ratio);
+    let background = match desc.background {
+        None => Background::default(),
+        Some(BackgroundDesc::None) => Background::None,
+        Some(BackgroundDesc::Solid { color }) => Background::Solid(Color::from(color)),
+        Some(BackgroundDesc::Gradient { bottom, top }) => Background::Gradient {
+            bottom: Color::from(bottom),
+            top: Color::from(top),
+        },
+    };
+
+    let mut textures = TextureBuilder {
+        source,
+        base_dir,
+        descs: &desc.textures,
+        built: HashMap::with_capacity(desc.textures.len()),
+        resolving: Vec::new(),
+        seed,
+    };
+    let mut materials = HashMap::with_capacity(desc.materials.len());
+    // In document order, so the first invalid material is the one reported.
+    let mut material_descs: Vec<_> = desc.materials.iter().collect();
+    material_descs.sort_by_key(|(_, material)| material.span().start);
+    for (name, material) in material_descs {
+        let built = build_material(material.get_ref(), &mut textures).map_err(|e| match e {
+            BuildError::Located(e) => e,
+            BuildError::Message(message) => located(source, material.span().start, message),
+        })?;
+        materials.insert(name.as_str(), built);
+    }
+
+    let mut models = ModelList::default();
+    let mut meshes = HashMap::new();
+    for object in &desc.objects {
+        let transform = object
+            .get_ref()
+            .transform
+            .as_ref()
+            .map(build_transform)
+            .transpose()
+            .map_err(|message| located(source, object.span().start, message))?;
+        let material = |name: &str| {
+            materials.get(name).map(Arc::clone).ok_or_else(|| {
+                located(
+                    source,
+                    object.span().start,
+                    format!("unknown material `{}`", name),
+                )
+            })
+        };
+
+        let check_radius = |radius: f32| {
+            if !radius.is_finite() || radius <= 0.0 {
+                return Err(located(
+                    source,
+                    object.span().start,
+                    format!("radius must be a positive number, got {}", radius),
+                ));
+            }
+            Ok(radius)
+        };
+
+        // Light sampling works in object space, which only keeps solid angles under uniform scales.
+        let stretched = object
+            .get_ref()
+            .transform
+            .as_ref()
+            .is_some_and(|transform| !transform.scale.is_uniform());
+        let is_light = |material: &Arc<dyn Material>| {
+            if material.is_emissive() && stretched {
+                return Err(located(
+                    source,
+                    object.span().start,
+                    "a light can only be scaled by the same factor on every axis",
+                ));
+            }
+            Ok(material.is_emissive())
+        };
+
+        match &object.get_ref().shape {
+            ObjectDesc::Sphere {
+                center,
+                radius,
+                material: name,
+            } => {
+                let material = material(name)?;
+                let emissive = is_light(&material)?;
+                push(
+                    &mut models,
+                    transform.as_ref(),
+                    Sphere::new(Vec3::from(*center), check_radius(*radius)?, material),
+                    emissive,
+                );
+            }
+            ObjectDesc::MovingSphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+                material: name,
+            } => {
+                let material = material(name)?;
+                let emissive = is_light(&material)?;
+                push(
+                    &mut models,
+                    transform.as_ref(),
+                    MovingSphere::new(
+                        (Vec3::from(*center0), *time0),
+                        (Vec3::from(*center1), *time1),
+                        check_radius(*radius)?,
+                        material,
+                    ),
+                    emissive,
+                );
+            }
+            ObjectDesc::Triangle {
+                vertices: [a, b, c],
+                material: name,
+            } => {
+                let material = material(name)?;
+                let emissive = is_light(&material)?;
+                push(
+                    &mut models,
+                    transform.as_ref(),
+                    Triangle::new(Vec3::from(*a), Vec3::from(*b), Vec3::from(*c), material),
+                    emissive,
+                );
+            }
+            ObjectDesc::Quad {
+                corner,
+                u,
+                v,
+                material: name,
+            } => {
+                let material = material(name)?;
+                let emissive = is_light(&material)?;
+                push(
+                    &mut models,
+                    transform.as_ref(),
+                    Quad::new(
+                        Vec3::from(*corner),
+                        Vec3::from(*u),
+                        Vec3::from(*v),
+                        material,
+                    ),
+                    emissive,
+                );
+            }
+            ObjectDesc::Disk {
+                center,
+                normal,
+                radius,
+                material: name,
+            } => {
+                let material = material(name)?;
+                let emissive = is_light(&material)?;
+                push(
+                    &mut models,
+                    transform.as_ref(),
+                    Disk::new(
+                        Vec3::from(*center),
+                        Vec3::from(*normal),
+                        check_radius(*radius)?,
+                        material,
+                    ),
+                    emissive,
+                );
+            }
+            ObjectDesc::Box {
+                min,
+                max,
+                material: name,
+            } => {
+                let material = material(name)?;
+                let emissive = is_light(&material)?;
+                push(
+                    &mut models,
+                    transform.as_ref(),
+                    Cuboid::new(Vec3::from(*min), Vec3::from(*max), material),
+                    emissive,
+                );
+            }
+            ObjectDesc::Plane {
+                point,
+                normal,
+                material: name,
+            } => {
+                let material = material(name)?;
+                if material.is_emissive() {
+                    return Err(located(
+                        source,
+                        object.span().start,
+                        "a plane can't be a light, it has no area to sample",
+                    ));
+                }
+                push(
+                    &mut models,
+                    transform.as_ref(),
+                    Plane::new(Vec3::from(*point), Vec3::from(*normal), material),
+                    false,
+                );
+            }
+            ObjectDesc::Mesh {
+                path,
+                material: name,
+            } => {
+                let meshes = match meshes.entry((path, name.as_deref())) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let material = name.as_deref().map(material).transpose()?;
+                        let loaded = load_mesh(&base_dir.join(path), material).map_err(|e| {
+                            located(source, object.span().start, format!("{:#}", e))
+                        })?;
+                        entry.insert(loaded)
+                    }
+                };
+                for mesh in meshes.iter() {
+                    match (mesh, &transform) {
+                        (LoadedMesh::Blas(blas), None) => models.push(Arc::clone(blas)),
+                        (LoadedMesh::Blas(blas), Some(transform)) => {
+                            models.push(Instance::new(Arc::clone(blas), *transform))
+                        }
+                        (LoadedMesh::Light(light), None) => models.push_light(Arc::clone(light)),
+                        (LoadedMesh::Light(light), Some(transform)) => {
+                            // Baked into a copy, light sampling doesn't go through instances of
+                            // stretched objects.
+                            let mesh = Arc::new(light.mesh().transformed(transform));
+                            let light = MeshLight::new(mesh).map_err(|e| {
+                                located(source, object.span().start, format!("{:#}", e))
+                            })?;
+                            models.push_light(light);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(Scene {
+        models,
+        camera,
+        background,
+    })
+}
+
+// The parts of an OBJ file, loaded once however many objects place it.
+enum LoadedMesh {
+    // Its own BVH, instanced into the scene's.
+    Blas(Arc<dyn Hittable>),
+    // Emissive, a single light over all its triangles. A transform is baked into a copy of the
+    // mesh for each object.
+    Light(Arc<MeshLight>),
+}
+
+fn load_mesh(path: &Path, material: Option<Arc<dyn Material>>) -> Result<Vec<LoadedMesh>> {
+    load_obj(path, material)?
+        .into_iter()
+        .map(|mesh| {
+            if mesh.material().is_emissive() {
+                return Ok(LoadedMesh::Light(Arc::new(MeshLight::new(mesh)?)));
+            }
+            let blas = Bvh::new(mesh.triangles().collect())?;
+            Ok(LoadedMesh::Blas(Arc::new(blas)))
+        })
+        .collect()
+}
+
+fn push(
+    models: &mut ModelList,
+    transform: Option<&Transform>,
+    hittable: impl Hittable + 'static,
+    emissive: bool,
+) {
+    let Some(transform) = transform else {
+        return push_model(models, hittable, emissive);
+    };
+    let instance = Instance::new(Arc::new(hittable), *transform);
+    push_model(models, instance, emissive);
+}
+
+fn push_model(models: &mut ModelList, hittable: impl Hittable + 'static, emissive: bool) {
+    if emissive {
+        models.push_light(hittable);
+    } else {
+        models.push(hittable);
+    }
+}
+
+// Scaled, then rotated about the x, y and z axes in turn, then translated.
+fn build_transform(desc: &TransformDesc) -> Result<Transform, String> {
+    let scale = match desc.scale {
+        ScaleDesc::Uniform(factor) => Vec3::repeat(factor),
+        ScaleDesc::PerAxis(factors) => Vec3::from(factors),
+    };
+    let finite = scale
+        .iter()
+        .chain(&desc.rotate)
+        .chain(&desc.translate)
+        .all(|v| v.is_finite());
+    if !finite {
+        return Err("transform values must be finite numbers".to_string());
+    }
+    let scaling = Transform::scaling(scale).ok_or("scale factors can't be zero")?;
+    let [x, y, z] = desc.rotate;
+    // Finite rotations and translations are always invertible.
+    Ok(scaling
+        .then(&Transform::rotation(Vec3::x(), x).unwrap())
+        .then(&Transform::rotation(Vec3::y(), y).unwrap())
+        .then(&Transform::rotation(Vec3::z(), z).unwrap())
+        .then(&Transform::translation(Vec3::from(desc.translate)).unwrap()))
+}
+
+fn build_camera(desc: &CameraDesc, aspect_ratio: f32) -> Camera {
+    let look_from = Vec3::from(desc.look_from);
+    let look_at = Vec3::from(desc.look_at);
+    let focus_dist = desc
+        .focus_dist
+        .unwrap_or_else(|| (look_from - look_at).magnitude());
+
+    Camera::new(
+        look_from,
+        look_at,
+        Vec3::from(desc.view_up),
+        desc.vertical_fov,
+        aspect_ratio,
+        desc.aperture,
+        focus_dist,
+    )
+    .with_shutter(desc.shutter[0], desc.shutter[1])
+}
+
+enum BuildError {
+    // Already points at the offending line.
+    Located(anyhow::Error),
+    // Still needs the location of whatever is being built.
+    Message(String),
+}
+
+struct TextureBuilder<'d> {
+    source: &'d str,
+    base_dir: &'d Path,
+    descs: &'d HashMap<String, Spanned<TextureDesc>>,
+    built: HashMap<&'d str, Arc<dyn Texture>>,
+    resolving: Vec<&'d str>,
+    // Each noise texture gets its own stream so it doesn't depend on the order textures are built in.
+    seed: u64,
+}
+
+impl<'d> TextureBuilder<'d> {
+    fn color_source(&mut self, color: &ColorSource) -> Result<Arc<dyn Texture>, BuildError> {
+        match color {
+            ColorSource::Color(color) => Ok(Arc::new(SolidColor::new(Color::from(*color)))),
+            ColorSource::Texture(name) => self.texture(name),
+        }
+    }
+
+    fn texture(&mut self, name: &str) -> Result<Arc<dyn Texture>, BuildError> {
+        if let Some(texture) = self.built.get(name) {
+            return Ok(Arc::clone(texture));
+        }
+        let (name, desc) = self
+            .descs
+            .get_key_value(name)
+            .ok_or_else(|| BuildError::Message(format!("unknown texture `{}`", name)))?;
+        if self.resolving.contains(&name.as_str()) {
+            return Err(BuildError::Message(format!(
+                "texture `{}` refers to itself",
+                name
+            )));
+        }
+
+        self.resolving.push(name);
+        let texture = self.build(name, desc.get_ref()).map_err(|e| match e {
+            BuildError::Located(e) => BuildError::Located(e),
+            BuildError::Message(message) => {
+                BuildError::Located(located(self.source, desc.span().start, message))
+            }
+        });
+        self.resolving.pop();
+
+        let texture = texture?;
+        self.built.insert(name, Arc::clone(&texture));
+        Ok(texture)
+    }
+
+    fn build(&mut self, name: &str, desc: &TextureDesc) -> Result<Arc<dyn Texture>, BuildError> {
+        Ok(match desc {
+            TextureDesc::Solid { color } => Arc::new(SolidColor::new(Color::from(*color))),
+            TextureDesc::Checker { scale, even, odd } => {
+                // TOML accepts `nan` and `inf`, which would make every lookup NaN.
+                if !scale.is_finite() || *scale <= 0.0 {
+                    return Err(BuildError::Message(format!(
+                        "checker scale must be a positive number, got {}",
+                        scale
+                    )));
+                }
+                Arc::new(CheckerTexture::new(
+                    *scale,
+                    self.color_source(even)?,
+                    self.color_source(odd)?,
+                ))
+            }
+            TextureDesc::Image {
+                path,
+                wrap,
+                filtering,
+            } => {
+                let wrap = match wrap {
+                    WrapModeDesc::Repeat => WrapMode::Repeat,
+                    WrapModeDesc::Clamp => WrapMode::Clamp,
+                    WrapModeDesc::Mirror => WrapMode::Mirror,
+                };
+                let filtering = match filtering {
+                    FilteringDesc::Nearest => Filtering::Nearest,
+                    FilteringDesc::Bilinear => Filtering::Bilinear,
+                };
+                Arc::new(
+                    ImageTexture::open(&self.base_dir.join(path), wrap, filtering)
+                        .map_err(|e| BuildError::Message(format!("{:#}", e)))?,
+                )
+            }
+            TextureDesc::Noise { scale, kind, color } => {
+                if !scale.is_finite() || *scale <= 0.0 {
+                    return Err(BuildError::Message(format!(
+                        "noise scale must be a positive number, got {}",
+                        scale
+                    )));
+                }
+                let kind = match kind {
+                    NoiseKindDesc::Noise => NoiseKind::Noise,
+                    NoiseKindDesc::Turbulence => NoiseKind::Turbulence,
+                    NoiseKindDesc::Marble => NoiseKind::Marble,
+                };
+                Arc::new(NoiseTexture::new(
+                    &mut stream_rng(self.seed, name_stream(name)),
+                    *scale,
+                    kind,
+                    Color::from(*color),
+                ))
+            }
+        })
+    }
+}
+
+fn build_material(
+    desc: &MaterialDesc,
+    textures: &mut TextureBuilder,
+) -> Result<Arc<dyn Material>, BuildError> {
+    Ok(match desc {
+        MaterialDesc::Lambertian { albedo } => {
+            Arc::new(Lambertian::from_texture(textures.color_source(albedo)?))
+        }
+        MaterialDesc::Metal { albedo, fuzzy } => {
+            if *fuzzy < 0.0 {
+                return Err(BuildError::Message(format!(
+                    "metal fuzziness must not be negative, got {}",
+                    fuzzy
+                )));
+            }
+            Arc::new(Metal::from_texture(textures.color_source(albedo)?, *fuzzy))
+        }
+        MaterialDesc::Dielectric {
+            index_of_refraction,
+        } => {
+            if *index_of_refraction <= 0.0 {
+                return Err(BuildError::Message(format!(
+                    "index of refraction must be positive, got {}",
+                    index_of_refraction
+                )));
+            }
+            Arc::new(Dielectric::new(*index_of_refraction))
+        }
+        MaterialDesc::DiffuseLight { emit } => {
+            Arc::new(DiffuseLight::new(textures.color_source(emit)?))
+        }
+    })
+}
+
+fn located(source: &str, offset: usize, message: impl fmt::Display) -> anyhow::Error {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+        .chars()
+        .count()
+        + 1;
+
+    anyhow!("line {}, column {}: {}", line, column, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMERA: &str = r#"
+[camera]
+look_from = [0.0, 0.0, 5.0]
+look_at = [0.0, 0.0, 0.0]
+vertical_fov = 40.0
+
+[materials.red]
+type = "lambertian"
+albedo = [0.8, 0.1, 0.1]
+"#;
+
+    // The error of a scene with `objects` after the camera and material above.
+    fn error(objects: &str) -> String {
+        match parse(&format!("{}{}", CAMERA, objects), Path::new(""), 1.5, 0) {
+            Ok(_) => panic!("parsed {}", objects),
+            Err(e) => format!("{:#}", e),
+        }
+    }
+
+    #[test]
+    fn bundled_scenes_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let source = std::fs::read_to_string(&path).unwrap();
+            if let Err(e) = parse(&source, &dir, 1.5, 0) {
+                panic!("{}: {:#}", path.display(), e);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_materials() {
+        let error = error(
+            r#"
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "blue"
+"#,
+        );
+        assert_eq!(error, "line 11, column 1: unknown material `blue`");
+    }
+
+    #[test]
+    fn rejects_non_finite_transforms() {
+        for transform in [
+            "translate = [nan, 0.0, 0.0]",
+            "scale = inf",
+            "rotate = [0.0, -inf, 0.0]",
+        ] {
+            let error = error(&format!(
+                r#"
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "red"
+transform = {{ {} }}
+"#,
+                transform
+            ));
+            assert_eq!(
+                error,
+                "line 11, column 1: transform values must be finite numbers"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_stretched_lights() {
+        let error = error(
+            r#"
+[materials.light]
+type = "diffuse_light"
+emit = [1.0, 1.0, 1.0]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "light"
+transform = { scale = [1.0, 2.0, 1.0] }
+"#,
+        );
+        assert_eq!(
+            error,
+            "line 15, column 1: a light can only be scaled by the same factor on every axis"
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_misspelled_fields() {
+        let error = error(
+            r#"
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+raduis = 1.0
+material = "red"
+"#,
+        );
+        assert!(error.contains("line 11"), "{}", error);
+        assert!(error.contains("raduis"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_bad_texture_scales() {
+        let checker = "type = \"checker\"\neven = [0.0, 0.0, 0.0]\nodd = [1.0, 1.0, 1.0]";
+        for (texture, scale) in [
+            (checker, "0.0"),
+            (checker, "nan"),
+            (checker, "inf"),
+            ("type = \"noise\"", "-1.0"),
+            ("type = \"noise\"", "nan"),
+        ] {
+            let error = error(&format!(
+                r#"
+[textures.pattern]
+{}
+scale = {}
+
+[materials.patterned]
+type = "lambertian"
+albedo = "pattern"
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "patterned"
+"#,
+                texture, scale
+            ));
+            assert!(
+                error.contains("scale must be a positive number"),
+                "{}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_radii() {
+        for (shape, radius) in [
+            ("type = \"sphere\"\ncenter = [0.0, 0.0, 0.0]", "0.0"),
+            ("type = \"sphere\"\ncenter = [0.0, 0.0, 0.0]", "nan"),
+            (
+                "type = \"moving_sphere\"\ncenter0 = [0.0, 0.0, 0.0]\ncenter1 = [1.0, 0.0, 0.0]",
+                "-1.0",
+            ),
+            (
+                "type = \"disk\"\ncenter = [0.0, 0.0, 0.0]\nnormal = [0.0, 1.0, 0.0]",
+                "inf",
+            ),
+        ] {
+            let error = error(&format!(
+                r#"
+[[objects]]
+{}
+radius = {}
+material = "red"
+"#,
+                shape, radius
+            ));
+            assert_eq!(
+                error,
+                format!(
+                    "line 11, column 1: radius must be a positive number, got {}",
+                    radius.parse::<f32>().unwrap()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn reports_the_first_invalid_material() {
+        let mut materials = String::new();
+        for name in ["b", "d", "a", "c"] {
+            materials.push_str(&format!(
+                "\n[materials.{}]\ntype = \"dielectric\"\nindex_of_refraction = -1.0\n",
+                name
+            ));
+        }
+        for _ in 0..8 {
+            assert_eq!(
+                error(&materials),
+                "line 11, column 1: index of refraction must be positive, got -1"
+            );
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Context, Result};
+use nalgebra::Vector2;
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    hittable::triangle::TriangleMesh,
+    materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal, Material},
+    vec::{Color, Vec3},
+};
+
+const DEFAULT_DIFFUSE: [f32; 3] = [0.8, 0.8, 0.8];
+const DEFAULT_IOR: f32 = 1.5;
+
+/// Loads every model of a Wavefront OBJ file as a triangle mesh.
+///
+/// Materials come from the MTL libraries referenced by the OBJ file unless `material` overrides them.
+pub fn load_obj(
+    path: &Path,
+    material: Option<Arc<dyn Material>>,
+) -> Result<Vec<Arc<TriangleMesh>>> {
+    let (models, mtl_materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
+        .with_context(|| format!("failed to load OBJ file `{}`", path.display()))?;
+
+    let materials = match material {
+        Some(_) => Vec::new(),
+        None => mtl_materials
+            .with_context(|| format!("failed to load MTL for `{}`", path.display()))?
+            .iter()
+            .map(mtl_material)
+            .collect(),
+    };
+    let fallback: Arc<dyn Material> =
+        material.unwrap_or_else(|| Arc::new(Lambertian::new(Color::from(DEFAULT_DIFFUSE))));
+
+    models
+        .into_iter()
+        .filter(|model| !model.mesh.indices.is_empty())
+        .map(|model| {
+            let material = model
+                .mesh
+                .material_id
+                .and_then(|id| materials.get(id))
+                .unwrap_or(&fallback);
+            to_triangle_mesh(model.mesh, Arc::clone(material))
+                .with_context(|| format!("invalid mesh `{}` in `{}`", model.name, path.display()))
+                .map(Arc::new)
+        })
+        .collect()
+}
+
+fn to_triangle_mesh(mesh: tobj::Mesh, material: Arc<dyn Material>) -> Result<TriangleMesh> {
+    let vertex_count = mesh.positions.len() / 3;
+    if let Some(index) = mesh.indices.iter().find(|&&i| i as usize >= vertex_count) {
+        return Err(anyhow!(
+            "vertex index {} out of range for {} vertices",
+            index,
+            vertex_count
+        ));
+    }
+
+    let positions = mesh
+        .positions
+        .chunks_exact(3)
+        .map(|p| Vec3::new(p[0], p[1], p[2]))
+        .collect();
+    let indices = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|i| [i[0], i[1], i[2]])
+        .collect();
+    let mut triangle_mesh = TriangleMesh::new(positions, indices, material);
+
+    if mesh.normals.len() == mesh.positions.len() {
+        triangle_mesh = triangle_mesh.with_normals(
+            mesh.normals
+                .chunks_exact(3)
+                .map(|n| Vec3::new(n[0], n[1], n[2]))
+                .collect(),
+        );
+    }
+    if mesh.texcoords.len() == vertex_count * 2 {
+        triangle_mesh = triangle_mesh.with_texcoords(
+            mesh.texcoords
+                .chunks_exact(2)
+                .map(|uv| Vector2::new(uv[0], uv[1]))
+                .collect(),
+        );
+    }
+
+    Ok(triangle_mesh)
+}
+
+fn mtl_material(mtl: &tobj::Material) -> Arc<dyn Material> {
+    let illum = mtl.illumination_model.unwrap_or(2);
+    let dissolve = mtl.dissolve.unwrap_or(1.0);
+
+    // Transparent: illum 4, 6, 7 and 9 use refraction or glass reflections.
+    if dissolve < 1.0 || matches!(illum, 4 | 6 | 7 | 9) {
+        return Arc::new(Dielectric::new(mtl.optical_density.unwrap_or(DEFAULT_IOR)));
+    }
+
+    let diffuse = Color::from(mtl.diffuse.unwrap_or(DEFAULT_DIFFUSE));
+    let specular = Color::from(mtl.specular.unwrap_or_default());
+
+    // Mirror-like: illum 3, 5 and 8 turn reflections on, otherwise trust a purely specular colour.
+    if matches!(illum, 3 | 5 | 8) || (diffuse.max() <= 0.0 && specular.max() > 0.0) {
+        let shininess = mtl.shininess.unwrap_or(0.0).max(0.0);
+        let fuzzy = (2.0 / (shininess + 2.0)).sqrt();
+        return Arc::new(Metal::new(specular, fuzzy));
+    }
+
+    Arc::new(Lambertian::new(diffuse))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::{hittable::Hittable, ray::Ray};
+
+    // Writes `files` to a directory of their own and returns it.
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("obj-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in files {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    fn hits(mesh: &Arc<TriangleMesh>, x: f32, y: f32) -> bool {
+        let ray = Ray::new(Vec3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        mesh.triangles()
+            .any(|triangle| triangle.hit(&ray, 0.001, f32::INFINITY).is_some())
+    }
+
+    #[test]
+    fn triangulates_polygons_and_resolves_negative_indices() {
+        let dir = write_files(
+            "polygons",
+            &[
+                (
+                    "shapes.obj",
+                    "mtllib shapes.mtl\n\
+                     o square\n\
+                     usemtl red\n\
+                     v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+                     f 1 2 3 4\n\
+                     o triangle\n\
+                     v 2 0 0\nv 3 0 0\nv 2 1 0\n\
+                     f -3 -2 -1\n",
+                ),
+                ("shapes.mtl", "newmtl red\nKd 0.8 0.1 0.1\n"),
+            ],
+        );
+        let meshes = load_obj(&dir.join("shapes.obj"), None).unwrap();
+
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].triangles().count(), 2);
+        assert!(hits(&meshes[0], 0.2, 0.8) && hits(&meshes[0], 0.8, 0.2));
+        assert!(!hits(&meshes[0], 1.5, 0.5));
+        assert_eq!(meshes[1].triangles().count(), 1);
+        assert!(hits(&meshes[1], 2.2, 0.2));
+        assert!(!hits(&meshes[1], 2.8, 0.8));
+    }
+
+    #[test]
+    fn missing_mtl_fails_unless_the_material_is_overridden() {
+        let dir = write_files(
+            "missing-mtl",
+            &[(
+                "mesh.obj",
+                "mtllib missing.mtl\nusemtl red\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+            )],
+        );
+        let path = dir.join("mesh.obj");
+
+        let error = match load_obj(&path, None) {
+            Ok(_) => panic!("loaded without its MTL"),
+            Err(e) => format!("{:#}", e),
+        };
+        assert!(error.contains("failed to load MTL"), "{}", error);
+
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::repeat(0.5)));
+        let meshes = load_obj(&path, Some(material)).unwrap();
+        assert_eq!(meshes.len(), 1);
+    }
+
+    #[test]
+    fn rejects_out_of_range_indices() {
+        let dir = write_files(
+            "out-of-range",
+            &[("mesh.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n")],
+        );
+        assert!(load_obj(&dir.join("mesh.obj"), None).is_err());
+    }
+}
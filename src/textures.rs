@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use crate::vec::{Color, Vec3};
+
+pub mod checker;
+pub mod image;
+pub mod noise;
+mod perlin;
+pub mod solid_color;
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Color;
+}
+
+impl<T: Texture + ?Sized> Texture for Arc<T> {
+    #[inline(always)]
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Color {
+        self.as_ref().value(u, v, point)
+    }
+}
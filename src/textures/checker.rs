@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::vec::{Color, Vec3};
+
+use super::Texture;
+
+pub struct CheckerTexture {
+    inv_scale: f32,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f32, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f32, v: f32, point: &Vec3) -> Color {
+        let cell = (self.inv_scale * point).map(|c| c.floor() as i64);
+        if (cell.x + cell.y + cell.z).rem_euclid(2) == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textures::solid_color::SolidColor;
+
+    #[test]
+    fn parity_flips_across_cells() {
+        let black = Color::zeros();
+        let white = Color::repeat(1.0);
+        let checker = CheckerTexture::new(
+            0.5,
+            Arc::new(SolidColor::new(white)),
+            Arc::new(SolidColor::new(black)),
+        );
+        let value = |x: f32, y: f32, z: f32| checker.value(0.0, 0.0, &Vec3::new(x, y, z));
+        assert_eq!(value(0.25, 0.25, 0.25), white);
+        // Across a cell boundary on each axis, and across the origin.
+        assert_eq!(value(0.49, 0.25, 0.25), white);
+        assert_eq!(value(0.51, 0.25, 0.25), black);
+        assert_eq!(value(0.25, 0.51, 0.25), black);
+        assert_eq!(value(0.25, 0.25, 0.51), black);
+        assert_eq!(value(-0.01, 0.25, 0.25), black);
+        // Two steps flip it back.
+        assert_eq!(value(0.51, 0.51, 0.25), white);
+        assert_eq!(value(1.01, 0.25, 0.25), white);
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use std::path::Path;
+
+use crate::vec::{Color, Vec3};
+
+use super::Texture;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub enum Filtering {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    texels: Vec<Color>,
+    wrap: WrapMode,
+    filtering: Filtering,
+}
+
+impl ImageTexture {
+    pub fn open(path: &Path, wrap: WrapMode, filtering: Filtering) -> Result<Self> {
+        let image = image::open(path)
+            .with_context(|| format!("failed to open texture `{}`", path.display()))?;
+        Ok(Self::from_image(&image, wrap, filtering))
+    }
+
+    pub fn from_image(image: &DynamicImage, wrap: WrapMode, filtering: Filtering) -> Self {
+        // Float images hold linear radiance already, everything else is sRGB encoded.
+        let is_linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let rgb = image.to_rgb32f();
+        let texels = rgb
+            .pixels()
+            .map(|p| {
+                let color = Color::new(p[0], p[1], p[2]);
+                if is_linear {
+                    color
+                } else {
+                    color.map(srgb_to_linear)
+                }
+            })
+            .collect();
+
+        Self {
+            width: rgb.width() as usize,
+            height: rgb.height() as usize,
+            texels,
+            wrap,
+            filtering,
+        }
+    }
+
+    #[inline(always)]
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = wrap(x, self.width, self.wrap);
+        let y = wrap(y, self.height, self.wrap);
+        self.texels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _: &Vec3) -> Color {
+        if self.texels.is_empty() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        // Image rows go top to bottom while v goes up.
+        let x = u * self.width as f32;
+        let y = (1.0 - v) * self.height as f32;
+
+        match self.filtering {
+            Filtering::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filtering::Bilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                (1.0 - fy) * ((1.0 - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0))
+                    + fy * ((1.0 - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1))
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn wrap(i: i64, size: usize, mode: WrapMode) -> usize {
+    let size = size as i64;
+    let wrapped = match mode {
+        WrapMode::Repeat => i.rem_euclid(size),
+        WrapMode::Clamp => i.clamp(0, size - 1),
+        WrapMode::Mirror => {
+            let period = i.rem_euclid(2 * size);
+            if period < size {
+                period
+            } else {
+                2 * size - 1 - period
+            }
+        }
+    };
+
+    wrapped as usize
+}
+
+#[inline(always)]
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    const TEXELS: [[f32; 3]; 4] = [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+        [1.0, 1.0, 1.0],
+    ];
+
+    // Red and green on the top row, blue and white below, stored as linear floats.
+    fn texture(wrap: WrapMode, filtering: Filtering) -> ImageTexture {
+        let image = ImageBuffer::from_fn(2, 2, |x, y| Rgb(TEXELS[(y * 2 + x) as usize]));
+        ImageTexture::from_image(&DynamicImage::ImageRgb32F(image), wrap, filtering)
+    }
+
+    fn texel(x: usize, y: usize) -> Color {
+        Color::from(TEXELS[y * 2 + x])
+    }
+
+    #[test]
+    fn wraps_outside_the_unit_square() {
+        let repeat = texture(WrapMode::Repeat, Filtering::Nearest);
+        let clamp = texture(WrapMode::Clamp, Filtering::Nearest);
+        let mirror = texture(WrapMode::Mirror, Filtering::Nearest);
+        // Texel columns 2, 3 and -1 along u, and rows 2 and -1 along v.
+        for (u, v, repeated, clamped, mirrored) in [
+            (1.25, 0.75, (0, 0), (1, 0), (1, 0)),
+            (1.75, 0.75, (1, 0), (1, 0), (0, 0)),
+            (-0.25, 0.75, (1, 0), (0, 0), (0, 0)),
+            (0.25, -0.25, (0, 0), (0, 1), (0, 1)),
+            (0.25, 1.25, (0, 1), (0, 0), (0, 0)),
+        ] {
+            let p = Vec3::zeros();
+            assert_eq!(
+                repeat.value(u, v, &p),
+                texel(repeated.0, repeated.1),
+                "{} {}",
+                u,
+                v
+            );
+            assert_eq!(
+                clamp.value(u, v, &p),
+                texel(clamped.0, clamped.1),
+                "{} {}",
+                u,
+                v
+            );
+            assert_eq!(
+                mirror.value(u, v, &p),
+                texel(mirrored.0, mirrored.1),
+                "{} {}",
+                u,
+                v
+            );
+        }
+    }
+
+    #[test]
+    fn bilinear_blends_neighbouring_texels() {
+        let texture = texture(WrapMode::Clamp, Filtering::Bilinear);
+        let p = Vec3::zeros();
+        let average = (texel(0, 0) + texel(1, 0) + texel(0, 1) + texel(1, 1)) / 4.0;
+        assert!((texture.value(0.5, 0.5, &p) - average).norm() < 1e-6);
+        // Texel centres come back unblended.
+        assert!((texture.value(0.25, 0.75, &p) - texel(0, 0)).norm() < 1e-6);
+        assert!((texture.value(0.75, 0.25, &p) - texel(1, 1)).norm() < 1e-6);
+        // Halfway between the two top texels.
+        let top = (texel(0, 0) + texel(1, 0)) / 2.0;
+        assert!((texture.value(0.5, 0.75, &p) - top).norm() < 1e-6);
+    }
+
+    #[test]
+    fn decodes_srgb_images() {
+        let image = ImageBuffer::from_pixel(1, 1, Rgb([255u8, 188, 0]));
+        let texture = ImageTexture::from_image(
+            &DynamicImage::ImageRgb8(image),
+            WrapMode::Repeat,
+            Filtering::Nearest,
+        );
+        let c = texture.value(0.5, 0.5, &Vec3::zeros());
+        assert!((c - Color::new(1.0, 0.5, 0.0)).norm() < 1e-2, "{:?}", c);
+    }
+}
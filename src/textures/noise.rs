@@ -0,0 +1,82 @@
+use rand::Rng;
+
+use crate::vec::{Color, Vec3};
+
+use super::{perlin::Perlin, Texture};
+
+const TURBULENCE_DEPTH: u32 = 7;
+
+#[derive(Copy, Clone, Debug)]
+pub enum NoiseKind {
+    Noise,
+    Turbulence,
+    Marble,
+}
+
+pub struct NoiseTexture {
+    perlin: Perlin,
+    scale: f32,
+    kind: NoiseKind,
+    color: Color,
+}
+
+impl NoiseTexture {
+    pub fn new<R: Rng>(rng: &mut R, scale: f32, kind: NoiseKind, color: Color) -> Self {
+        Self {
+            perlin: Perlin::new(rng),
+            scale,
+            kind,
+            color,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _: f32, _: f32, point: &Vec3) -> Color {
+        let p = self.scale * point;
+        let intensity = match self.kind {
+            NoiseKind::Noise => 0.5 * (1.0 + self.perlin.noise(&p)),
+            NoiseKind::Turbulence => self.perlin.turbulence(&p, TURBULENCE_DEPTH),
+            NoiseKind::Marble => {
+                0.5 * (1.0 + (p.z + 10.0 * self.perlin.turbulence(point, TURBULENCE_DEPTH)).sin())
+            }
+        };
+
+        intensity * self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::stream_rng;
+
+    #[test]
+    fn stays_within_the_colour() {
+        let color = Color::new(1.0, 0.5, 0.25);
+        for kind in [NoiseKind::Noise, NoiseKind::Turbulence, NoiseKind::Marble] {
+            let texture = NoiseTexture::new(&mut stream_rng(1, 0), 4.0, kind, color);
+            let same = NoiseTexture::new(&mut stream_rng(1, 0), 4.0, kind, color);
+            let mut rng = stream_rng(2, 0);
+            let mut values = Vec::new();
+            for _ in 0..1000 {
+                let p = Vec3::new(rng.gen(), rng.gen(), rng.gen()) * 10.0 - Vec3::repeat(5.0);
+                let c = texture.value(0.0, 0.0, &p);
+                assert_eq!(c, same.value(0.0, 0.0, &p), "{:?}", kind);
+                // Turbulence sums octaves and may exceed the colour a little.
+                let bound = if matches!(kind, NoiseKind::Turbulence) {
+                    2.0
+                } else {
+                    1.0
+                };
+                assert!(c.x >= 0.0 && c.x <= bound * color.x, "{:?} {:?}", kind, c);
+                assert!((c.y / c.x - 0.5).abs() < 1e-4 || c.x < 1e-6, "{:?}", kind);
+                values.push(c.x);
+            }
+            // Not a constant.
+            let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+            let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            assert!(max - min > 0.2, "{:?}", kind);
+        }
+    }
+}
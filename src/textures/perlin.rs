@@ -0,0 +1,93 @@
+use rand::{seq::SliceRandom, Rng};
+
+use crate::vec::{random_vec, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new<R: Rng>(rng: &mut R) -> Self {
+        let gradients = (0..POINT_COUNT)
+            .map(|_| random_vec(rng, -1.0..=1.0).normalize())
+            .collect();
+
+        Self {
+            gradients,
+            perm_x: permutation(rng),
+            perm_y: permutation(rng),
+            perm_z: permutation(rng),
+        }
+    }
+
+    pub fn noise(&self, point: &Vec3) -> f32 {
+        let floor = point.map(f32::floor);
+        let frac = point - floor;
+        let (i, j, k) = (floor.x as i64, floor.y as i64, floor.z as i64);
+
+        let mut corners = [[[Vec3::zeros(); 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[wrap(i + di as i64)]
+                        ^ self.perm_y[wrap(j + dj as i64)]
+                        ^ self.perm_z[wrap(k + dk as i64)];
+                    *corner = self.gradients[index];
+                }
+            }
+        }
+
+        interpolate(&corners, &frac)
+    }
+
+    pub fn turbulence(&self, point: &Vec3, depth: u32) -> f32 {
+        let mut accum = 0.0;
+        let mut p = *point;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+
+        accum.abs()
+    }
+}
+
+#[inline(always)]
+fn wrap(i: i64) -> usize {
+    (i & (POINT_COUNT as i64 - 1)) as usize
+}
+
+fn permutation<R: Rng>(rng: &mut R) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+    p.shuffle(rng);
+    p
+}
+
+fn interpolate(corners: &[[[Vec3; 2]; 2]; 2], frac: &Vec3) -> f32 {
+    // Hermite smoothing hides the grid artifacts of plain trilinear interpolation.
+    let smooth = frac.map(|t| t * t * (3.0 - 2.0 * t));
+    let mut accum = 0.0;
+
+    for (i, plane) in corners.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f32, j as f32, k as f32);
+                let weight = Vec3::new(frac.x - fi, frac.y - fj, frac.z - fk);
+                accum += (fi * smooth.x + (1.0 - fi) * (1.0 - smooth.x))
+                    * (fj * smooth.y + (1.0 - fj) * (1.0 - smooth.y))
+                    * (fk * smooth.z + (1.0 - fk) * (1.0 - smooth.z))
+                    * gradient.dot(&weight);
+            }
+        }
+    }
+
+    accum
+}
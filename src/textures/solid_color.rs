@@ -0,0 +1,20 @@
+use crate::vec::{Color, Vec3};
+
+use super::Texture;
+
+pub struct SolidColor {
+    color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for SolidColor {
+    #[inline(always)]
+    fn value(&self, _: f32, _: f32, _: &Vec3) -> Color {
+        self.color
+    }
+}
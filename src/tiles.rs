@@ -0,0 +1,182 @@
+use clap::ValueEnum;
+use std::time::Duration;
+
+/// The order tiles are handed out to the render threads in.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TileOrder {
+    /// Row by row from the top left.
+    Scanline,
+    /// Outwards from the centre of the image, where the subject usually is.
+    #[default]
+    Spiral,
+    /// Along a Hilbert curve, which keeps consecutive tiles next to each other.
+    Hilbert,
+}
+
+/// A rectangle of pixels, with `y` going down from the top of the image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// The tile with `margin` more pixels on every side, clipped to `bounds`.
+    pub fn grow(&self, margin: u32, bounds: &Tile) -> Tile {
+        let x = self.x.saturating_sub(margin).max(bounds.x);
+        let y = self.y.saturating_sub(margin).max(bounds.y);
+        Tile {
+            x,
+            y,
+            width: (self.x + self.width + margin).min(bounds.x + bounds.width) - x,
+            height: (self.y + self.height + margin).min(bounds.y + bounds.height) - y,
+        }
+    }
+}
+
+/// What it took to render a tile.
+#[derive(Copy, Clone, Debug)]
+pub struct TileStats {
+    pub tile: Tile,
+    pub samples: u64,
+    pub rays: u64,
+    pub elapsed: Duration,
+}
+
+/// Splits `region` into tiles of at most `size` pixels square, in `order`.
+pub fn tiles(region: &Tile, size: u32, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let columns = region.width.div_ceil(size);
+    let rows = region.height.div_ceil(size);
+
+    let mut grid: Vec<(u32, u32)> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect();
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let center_x = (columns as f32 - 1.0) / 2.0;
+            let center_y = (rows as f32 - 1.0) / 2.0;
+            // Ring by ring around the centre, each ring walked by angle.
+            let key = |&(column, row): &(u32, u32)| {
+                let (dx, dy) = (column as f32 - center_x, row as f32 - center_y);
+                (dx.abs().max(dy.abs()), dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+        }
+        TileOrder::Hilbert => {
+            let n = columns.max(rows).next_power_of_two();
+            grid.sort_by_key(|&(column, row)| hilbert_index(n, column, row));
+        }
+    }
+
+    grid.into_iter()
+        .map(|(column, row)| {
+            let (x, y) = (column * size, row * size);
+            Tile {
+                x: region.x + x,
+                y: region.y + y,
+                width: size.min(region.width - x),
+                height: size.min(region.height - y),
+            }
+        })
+        .collect()
+}
+
+// Distance along the Hilbert curve filling an `n` by `n` grid, `n` a power of two.
+fn hilbert_index(n: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+        // Rotate the quadrant so the curve continues where the previous one ended.
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(x: u32, y: u32, width: u32, height: u32) -> Tile {
+        Tile {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn every_order_covers_each_pixel_once() {
+        let regions = [
+            region(0, 0, 64, 64),
+            region(0, 0, 100, 37),
+            region(5, 11, 13, 90),
+            region(3, 2, 1, 1),
+            region(0, 0, 7, 7),
+        ];
+        for order in TileOrder::value_variants() {
+            for region in regions {
+                for size in [1, 5, 16, 200] {
+                    let tiles = tiles(&region, size, *order);
+                    let mut covered = vec![0; region.pixels() as usize];
+                    for tile in &tiles {
+                        assert!(tile.width >= 1 && tile.width <= size);
+                        assert!(tile.height >= 1 && tile.height <= size);
+                        for y in tile.y..tile.y + tile.height {
+                            for x in tile.x..tile.x + tile.width {
+                                assert!(x >= region.x && x < region.x + region.width);
+                                assert!(y >= region.y && y < region.y + region.height);
+                                covered[((y - region.y) * region.width + x - region.x) as usize] +=
+                                    1;
+                            }
+                        }
+                    }
+                    assert!(
+                        covered.iter().all(|&n| n == 1),
+                        "{:?} order of {:?} in tiles of {}",
+                        order,
+                        region,
+                        size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hilbert_tiles_follow_each_other() {
+        for n in [8, 16] {
+            let tiles = tiles(&region(0, 0, n, n), 1, TileOrder::Hilbert);
+            for pair in tiles.windows(2) {
+                let distance = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+                assert_eq!(distance, 1, "{:?} then {:?}", pair[0], pair[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn spirals_start_in_the_centre() {
+        let tiles = tiles(&region(0, 0, 50, 30), 10, TileOrder::Spiral);
+        assert_eq!((tiles[0].x, tiles[0].y), (20, 10));
+    }
+}
@@ -0,0 +1,252 @@
+use clap::ValueEnum;
+use image::{ImageBuffer, Rgb, RgbImage};
+use nalgebra::Matrix3;
+
+use crate::{
+    framebuffer::Framebuffer,
+    vec::{luminance, Color},
+};
+
+// Log2 range of the AgX base encoding, around middle grey.
+const AGX_MIN_EV: f32 = -12.473_931;
+const AGX_MAX_EV: f32 = 4.026_069;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ToneOperator {
+    #[default]
+    Clamp,
+    Reinhard,
+    ExtendedReinhard,
+    Aces,
+    Agx,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ToneMapping {
+    pub operator: ToneOperator,
+    // In stops, the radiance is scaled by `2^exposure` before the operator.
+    pub exposure: f32,
+    // Luminance mapped to pure white by the extended Reinhard operator.
+    pub white: f32,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            operator: ToneOperator::default(),
+            exposure: 0.0,
+            white: 4.0,
+        }
+    }
+}
+
+impl ToneMapping {
+    /// Maps linear scene radiance to linear display values in `[0, 1]`.
+    pub fn apply(&self, color: Color) -> Color {
+        let color = color.map(|c| c.max(0.0)) * self.exposure.exp2();
+        let mapped = match self.operator {
+            ToneOperator::Clamp => color,
+            ToneOperator::Reinhard => scale_luminance(color, |l| l / (1.0 + l)),
+            ToneOperator::ExtendedReinhard => {
+                let white_squared = self.white * self.white;
+                scale_luminance(color, |l| l * (1.0 + l / white_squared) / (1.0 + l))
+            }
+            ToneOperator::Aces => color.map(aces),
+            ToneOperator::Agx => agx(color),
+        };
+
+        mapped.map(|c| c.clamp(0.0, 1.0))
+    }
+}
+
+/// Tone maps the framebuffer and encodes it as 8-bit sRGB.
+pub fn tonemap(framebuffer: &Framebuffer, tone_mapping: &ToneMapping) -> RgbImage {
+    ImageBuffer::from_fn(framebuffer.width(), framebuffer.height(), |x, y| {
+        let c = tone_mapping
+            .apply(framebuffer.get(x, y))
+            .map(|c| (linear_to_srgb(c) * 255.0).round() as u8);
+        Rgb([c.x, c.y, c.z])
+    })
+}
+
+#[inline(always)]
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[inline(always)]
+fn scale_luminance(color: Color, f: impl Fn(f32) -> f32) -> Color {
+    let l = luminance(&color);
+    if l <= 0.0 {
+        return Color::zeros();
+    }
+    color * (f(l) / l)
+}
+
+// Krzysztof Narkowicz's fit of the ACES reference rendering transform.
+#[inline(always)]
+fn aces(x: f32) -> f32 {
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+// The minimal AgX approximation: inset into a wider gamut, a log encoding shaped by a polynomial
+// sigmoid, then back out and linearized again.
+fn agx(color: Color) -> Color {
+    #[rustfmt::skip]
+    let inset = Matrix3::new(
+        0.842_479_1, 0.078_433_6, 0.079_223_75,
+        0.042_328_24, 0.878_468_6, 0.079_166_13,
+        0.042_375_65, 0.078_433_6, 0.879_143,
+    );
+    #[rustfmt::skip]
+    let outset = Matrix3::new(
+        1.196_879, -0.098_020_88, -0.099_029_74,
+        -0.052_896_85, 1.151_903_1, -0.098_961_18,
+        -0.052_971_64, -0.098_043_45, 1.151_073_7,
+    );
+
+    let encoded = (inset * color).map(|c| {
+        let ev = c.max(1e-10).log2().clamp(AGX_MIN_EV, AGX_MAX_EV);
+        let x = (ev - AGX_MIN_EV) / (AGX_MAX_EV - AGX_MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    });
+
+    (outset * encoded).map(|c| c.max(0.0).powf(2.2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_curve() {
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-6);
+        // Both pieces meet at the threshold.
+        let below = linear_to_srgb(0.003_130_8);
+        let above = 1.055 * 0.003_130_8f32.powf(1.0 / 2.4) - 0.055;
+        assert!((below - above).abs() < 1e-6);
+        // Middle grey.
+        assert!((linear_to_srgb(0.18) - 0.4613).abs() < 1e-3);
+        let mut last = 0.0;
+        for i in 1..=1000 {
+            let c = linear_to_srgb(i as f32 / 1000.0);
+            assert!(c > last);
+            last = c;
+        }
+    }
+
+    fn mapping(operator: ToneOperator) -> ToneMapping {
+        ToneMapping {
+            operator,
+            ..ToneMapping::default()
+        }
+    }
+
+    #[test]
+    fn operators_map_into_the_unit_range() {
+        for operator in ToneOperator::value_variants() {
+            let mapping = mapping(*operator);
+            assert_eq!(
+                mapping.apply(Color::zeros()),
+                Color::zeros(),
+                "{:?}",
+                operator
+            );
+            let mut last = 0.0;
+            for i in 1..=200 {
+                let grey = 1.1f32.powi(i - 100);
+                let c = mapping.apply(Color::repeat(grey));
+                assert!(c.iter().all(|c| (0.0..=1.0).contains(c)), "{:?}", operator);
+                assert!(c.x >= last, "{:?} at {}", operator, grey);
+                last = c.x;
+            }
+            let c = mapping.apply(Color::new(1e6, 0.5, 1e-6));
+            assert!(c.iter().all(|c| (0.0..=1.0).contains(c)), "{:?}", operator);
+            // Negative values are treated as black.
+            let c = mapping.apply(Color::new(-1.0, 0.5, -1.0));
+            assert!(c.iter().all(|c| (0.0..=1.0).contains(c)), "{:?}", operator);
+        }
+    }
+
+    #[test]
+    fn reinhard_compresses_luminance() {
+        let mapping = mapping(ToneOperator::Reinhard);
+        let c = mapping.apply(Color::repeat(1.0));
+        assert!((c - Color::repeat(0.5)).norm() < 1e-6);
+        // Bright values approach white without reaching it.
+        let c = mapping.apply(Color::repeat(1000.0));
+        assert!(c.x < 1.0 && c.x > 0.99);
+        // The hue is kept, only the luminance is scaled.
+        let c = mapping.apply(Color::new(2.0, 1.0, 0.5));
+        assert!((c.x / c.y - 2.0).abs() < 1e-5 && (c.y / c.z - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn extended_reinhard_maps_white_to_one() {
+        for white in [1.0, 4.0, 16.0] {
+            let mapping = ToneMapping {
+                operator: ToneOperator::ExtendedReinhard,
+                exposure: 0.0,
+                white,
+            };
+            let c = mapping.apply(Color::repeat(white));
+            assert!((c - Color::repeat(1.0)).norm() < 1e-5, "{}", white);
+            let c = mapping.apply(Color::repeat(white / 2.0));
+            assert!(c.x < 1.0, "{}", white);
+        }
+    }
+
+    #[test]
+    fn exposure_scales_by_powers_of_two() {
+        for operator in ToneOperator::value_variants() {
+            for exposure in [-2.0, -0.5, 1.0, 3.0] {
+                let exposed = ToneMapping {
+                    exposure,
+                    ..mapping(*operator)
+                };
+                let color = Color::new(0.02, 0.05, 0.01);
+                let expected = mapping(*operator).apply(color * exposure.exp2());
+                let c = exposed.apply(color);
+                assert!(
+                    (c - expected).norm() < 1e-6,
+                    "{:?} at {}",
+                    operator,
+                    exposure
+                );
+            }
+        }
+        let brighter = ToneMapping {
+            exposure: 1.0,
+            ..mapping(ToneOperator::Clamp)
+        };
+        assert!((brighter.apply(Color::repeat(0.2)) - Color::repeat(0.4)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn aces_and_agx_are_filmic() {
+        // A toe that darkens the shadows and a shoulder that reaches white.
+        for operator in [ToneOperator::Aces, ToneOperator::Agx] {
+            let mapping = mapping(operator);
+            assert!(
+                mapping.apply(Color::repeat(0.01)).x < 0.01,
+                "{:?}",
+                operator
+            );
+            let grey = mapping.apply(Color::repeat(0.18)).x;
+            assert!(grey > 0.1 && grey < 0.3, "{:?}", operator);
+            assert!(
+                mapping.apply(Color::repeat(100.0)).x > 0.95,
+                "{:?}",
+                operator
+            );
+        }
+    }
+}
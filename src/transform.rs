@@ -0,0 +1,230 @@
+//! Affine transforms for placing objects, see [`crate::hittable::instance::Instance`].
+
+use nalgebra::{Matrix3, Matrix4, Point3, Unit};
+
+use crate::{hittable::aabb::Aabb, vec::Vec3};
+
+/// An invertible affine transform with its inverse and the matrix that carries normals.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+    // Inverse transpose of the linear part, normals stay perpendicular to transformed surfaces.
+    normal_matrix: Matrix3<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+            normal_matrix: Matrix3::identity(),
+        }
+    }
+
+    /// `None` if `matrix` isn't finite and invertible or its bottom row isn't `[0, 0, 0, 1]`.
+    pub fn from_matrix(matrix: Matrix4<f32>) -> Option<Self> {
+        if matrix.row(3) != Matrix4::<f32>::identity().row(3) {
+            return None;
+        }
+        let inverse = matrix.try_inverse()?;
+        if !matrix.iter().chain(inverse.iter()).all(|c| c.is_finite()) {
+            return None;
+        }
+        Some(Self {
+            matrix,
+            inverse,
+            normal_matrix: inverse.fixed_view::<3, 3>(0, 0).transpose(),
+        })
+    }
+
+    /// `None` if the offset isn't finite.
+    pub fn translation(offset: Vec3) -> Option<Self> {
+        Self::from_matrix(Matrix4::new_translation(&offset))
+    }
+
+    /// Counterclockwise by `degrees` looking down `axis`. `None` if `axis` is zero or either isn't
+    /// finite.
+    pub fn rotation(axis: Vec3, degrees: f32) -> Option<Self> {
+        let axis = Unit::try_new(axis, 0.0)?;
+        Self::from_matrix(Matrix4::from_axis_angle(&axis, degrees.to_radians()))
+    }
+
+    /// `None` if a factor is zero or isn't finite.
+    pub fn scaling(factors: Vec3) -> Option<Self> {
+        Self::from_matrix(Matrix4::new_nonuniform_scaling(&factors))
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: &Transform) -> Self {
+        Self {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+            normal_matrix: next.normal_matrix * self.normal_matrix,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+            normal_matrix: self.matrix.fixed_view::<3, 3>(0, 0).transpose(),
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4<f32> {
+        &self.matrix
+    }
+
+    #[inline(always)]
+    pub fn point(&self, point: &Vec3) -> Vec3 {
+        self.matrix.transform_point(&Point3::from(*point)).coords
+    }
+
+    #[inline(always)]
+    pub fn vector(&self, vector: &Vec3) -> Vec3 {
+        self.matrix.transform_vector(vector)
+    }
+
+    /// The normal of the transformed surface, not normalized.
+    #[inline(always)]
+    pub fn normal(&self, normal: &Vec3) -> Vec3 {
+        self.normal_matrix * normal
+    }
+
+    #[inline(always)]
+    pub fn inverse_point(&self, point: &Vec3) -> Vec3 {
+        self.inverse.transform_point(&Point3::from(*point)).coords
+    }
+
+    #[inline(always)]
+    pub fn inverse_vector(&self, vector: &Vec3) -> Vec3 {
+        self.inverse.transform_vector(vector)
+    }
+
+    /// The box around the transformed corners of `aabb`.
+    pub fn aabb(&self, aabb: &Aabb) -> Aabb {
+        let corner = |i: usize| {
+            let pick = |axis: usize| {
+                if i & (1 << axis) == 0 {
+                    aabb.min[axis]
+                } else {
+                    aabb.max[axis]
+                }
+            };
+            self.point(&Vec3::new(pick(0), pick(1), pick(2)))
+        };
+        (1..8).map(corner).fold(
+            Aabb {
+                min: corner(0),
+                max: corner(0),
+            },
+            |acc, p| Aabb {
+                min: acc.min.inf(&p),
+                max: acc.max.sup(&p),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Vec3, b: &Vec3) {
+        assert!((a - b).amax() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn sample() -> Transform {
+        Transform::scaling(Vec3::new(2.0, 1.0, 0.5))
+            .unwrap()
+            .then(&Transform::rotation(Vec3::new(1.0, 2.0, 3.0), 40.0).unwrap())
+            .then(&Transform::translation(Vec3::new(1.0, -2.0, 3.0)).unwrap())
+    }
+
+    #[test]
+    fn then_applies_in_order() {
+        let t = Transform::scaling(Vec3::repeat(2.0))
+            .unwrap()
+            .then(&Transform::translation(Vec3::new(1.0, 0.0, 0.0)).unwrap());
+        assert_close(
+            &t.point(&Vec3::new(1.0, 1.0, 1.0)),
+            &Vec3::new(3.0, 2.0, 2.0),
+        );
+        // Vectors aren't translated.
+        assert_close(
+            &t.vector(&Vec3::new(1.0, 1.0, 1.0)),
+            &Vec3::new(2.0, 2.0, 2.0),
+        );
+
+        let r = Transform::rotation(Vec3::z(), 90.0).unwrap();
+        assert_close(&r.point(&Vec3::x()), &Vec3::y());
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let t = sample();
+        let p = Vec3::new(0.3, -1.2, 4.0);
+        assert_close(&t.inverse_point(&t.point(&p)), &p);
+        assert_close(&t.inverse().point(&t.point(&p)), &p);
+        assert_close(&t.inverse_vector(&t.vector(&p)), &p);
+        assert_close(&t.then(&t.inverse()).point(&p), &p);
+    }
+
+    #[test]
+    fn normals_stay_perpendicular() {
+        let t = sample();
+        let (tangent, bitangent) = (Vec3::new(1.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 1.0));
+        let normal = tangent.cross(&bitangent);
+        let transformed = t.normal(&normal);
+        assert!(transformed.dot(&t.vector(&tangent)).abs() < 1e-4);
+        assert!(transformed.dot(&t.vector(&bitangent)).abs() < 1e-4);
+        // Same direction as the normal of the transformed surface.
+        let expected = t.vector(&tangent).cross(&t.vector(&bitangent));
+        assert!(transformed.normalize().dot(&expected.normalize()) > 0.9999);
+    }
+
+    #[test]
+    fn rejects_singular_matrices() {
+        assert!(Transform::scaling(Vec3::new(1.0, 0.0, 1.0)).is_none());
+        let mut projective = Matrix4::identity();
+        projective[(3, 0)] = 1.0;
+        assert!(Transform::from_matrix(projective).is_none());
+    }
+
+    #[test]
+    fn rejects_non_finite_values() {
+        assert!(Transform::translation(Vec3::new(f32::NAN, 0.0, 0.0)).is_none());
+        assert!(Transform::scaling(Vec3::repeat(f32::INFINITY)).is_none());
+        assert!(Transform::rotation(Vec3::zeros(), 30.0).is_none());
+        assert!(Transform::rotation(Vec3::y(), f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn aabb_holds_the_transformed_box() {
+        let t = sample();
+        let aabb = Aabb {
+            min: Vec3::new(-1.0, 0.0, 2.0),
+            max: Vec3::new(1.0, 3.0, 2.5),
+        };
+        let bounds = t.aabb(&aabb);
+        for i in 0..=10 {
+            let f = i as f32 / 10.0;
+            let p = t.point(&(aabb.min + f * (aabb.max - aabb.min)));
+            assert!(p
+                .iter()
+                .zip(bounds.min.iter())
+                .all(|(p, min)| p >= &(min - 1e-4)));
+            assert!(p
+                .iter()
+                .zip(bounds.max.iter())
+                .all(|(p, max)| p <= &(max + 1e-4)));
+        }
+    }
+}
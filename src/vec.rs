@@ -1,8 +1,9 @@
-use nalgebra::Vector3;
+use nalgebra::{Vector2, Vector3};
 use rand::{distributions::uniform::SampleRange, Rng};
 
 pub type Color = Vector3<f32>;
 pub type Vec3 = Vector3<f32>;
+pub type Vec2 = Vector2<f32>;
 
 #[inline(always)]
 pub fn random_vec<R: Rng, S: SampleRange<f32> + Clone>(rng: &mut R, range: S) -> Vec3 {
@@ -12,3 +13,23 @@ pub fn random_vec<R: Rng, S: SampleRange<f32> + Clone>(rng: &mut R, range: S) ->
         rng.gen_range(range),
     )
 }
+
+// Two unit vectors that complete `w` (unit length) to an orthonormal basis.
+#[inline(always)]
+pub fn orthonormal_basis(w: &Vec3) -> (Vec3, Vec3) {
+    let a = if w.x.abs() > 0.9 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let v = w.cross(&a).normalize();
+    let u = w.cross(&v);
+
+    (u, v)
+}
+
+/// Relative luminance of a linear Rec. 709 color.
+#[inline(always)]
+pub fn luminance(color: &Color) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use ray_tracing_one_weekend::{aov::Aov, scene::file, RenderSettings, Renderer};
+
+// Two quads filling the view meet at x = 0, straight through the middle of pixel column 1.
+const SCENE: &str = r#"
+[camera]
+look_from = [0.0, 0.0, 4.0]
+look_at = [0.0, 0.0, 0.0]
+vertical_fov = 40.0
+
+[materials.left]
+type = "lambertian"
+albedo = [0.8, 0.2, 0.2]
+
+[materials.right]
+type = "lambertian"
+albedo = [0.2, 0.2, 0.8]
+
+[[objects]]
+type = "quad"
+corner = [-10.0, -10.0, 0.0]
+u = [10.0, 0.0, 0.0]
+v = [0.0, 20.0, 0.0]
+material = "left"
+
+[[objects]]
+type = "quad"
+corner = [0.0, -10.0, 0.0]
+u = [10.0, 0.0, 0.0]
+v = [0.0, 20.0, 0.0]
+material = "right"
+"#;
+
+#[test]
+fn material_ids_name_a_material_of_the_pixel() {
+    let settings = RenderSettings {
+        width: 4,
+        height: 4,
+        samples_per_pixel: 64,
+        seed: 1,
+        integrator: Aov::MaterialId,
+        aovs: vec![Aov::MaterialId, Aov::Albedo],
+        ..RenderSettings::default()
+    };
+    let scene = file::parse(SCENE, Path::new(""), settings.aspect_ratio(), settings.seed).unwrap();
+    let output = Renderer::new().render_output(&scene, &settings).unwrap();
+
+    let (_, layer) = &output.aovs[0];
+    for framebuffer in [&output.framebuffer, layer] {
+        for y in 0..4 {
+            for x in 0..4 {
+                let id = framebuffer.get(x, y).x;
+                assert!(id == 1.0 || id == 2.0, "pixel ({}, {}) has id {}", x, y, id);
+            }
+        }
+    }
+
+    // Averaged layers still blend the two sides.
+    let (_, albedo) = &output.aovs[1];
+    let straddling = albedo.get(1, 1);
+    assert!(straddling.x > 0.3 && straddling.x < 0.7, "{:?}", straddling);
+}
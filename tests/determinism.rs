@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use ray_tracing_one_weekend::{
+    filter::{Filter, FilterKind},
+    scene::file,
+    tiles::TileOrder,
+    Framebuffer, RenderSettings, Renderer, Scene,
+};
+
+// Diffuse, glossy, glass and a small light, so paths take every kind of bounce.
+const SCENE: &str = r#"
+[camera]
+look_from = [0.0, 1.0, 6.0]
+look_at = [0.0, 0.5, 0.0]
+vertical_fov = 40.0
+aperture = 0.05
+
+[background]
+type = "gradient"
+bottom = [0.3, 0.3, 0.3]
+top = [0.2, 0.3, 0.6]
+
+[materials.ground]
+type = "lambertian"
+albedo = [0.6, 0.6, 0.6]
+
+[materials.metal]
+type = "metal"
+albedo = [0.8, 0.7, 0.6]
+fuzzy = 0.2
+
+[materials.glass]
+type = "dielectric"
+index_of_refraction = 1.5
+
+[materials.light]
+type = "diffuse_light"
+emit = [8.0, 8.0, 8.0]
+
+[[objects]]
+type = "quad"
+corner = [-4.0, 0.0, 4.0]
+u = [8.0, 0.0, 0.0]
+v = [0.0, 0.0, -8.0]
+material = "ground"
+
+[[objects]]
+type = "sphere"
+center = [-1.1, 0.5, 0.0]
+radius = 0.5
+material = "metal"
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.5, 0.5]
+radius = 0.5
+material = "glass"
+
+[[objects]]
+type = "box"
+min = [0.7, 0.0, -0.5]
+max = [1.5, 0.8, 0.3]
+material = "ground"
+transform = { rotate = [0.0, 20.0, 0.0] }
+
+[[objects]]
+type = "disk"
+center = [0.0, 2.5, 0.0]
+normal = [0.0, -1.0, 0.0]
+radius = 0.4
+material = "light"
+"#;
+
+fn settings() -> RenderSettings {
+    RenderSettings {
+        width: 48,
+        height: 32,
+        samples_per_pixel: 8,
+        max_depth: 8,
+        seed: 7,
+        ..RenderSettings::default()
+    }
+}
+
+fn scene(settings: &RenderSettings) -> Scene {
+    file::parse(SCENE, Path::new(""), settings.aspect_ratio(), settings.seed).unwrap()
+}
+
+fn render(settings: &RenderSettings, threads: usize) -> Framebuffer {
+    let scene = scene(settings);
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap()
+        .install(|| Renderer::new().render(&scene, settings).unwrap())
+}
+
+fn assert_same(a: &Framebuffer, b: &Framebuffer) {
+    assert_eq!((a.width(), a.height()), (b.width(), b.height()));
+    for (i, (a, b)) in a.pixels().iter().zip(b.pixels()).enumerate() {
+        assert_eq!(a, b, "pixel {} differs", i);
+    }
+}
+
+#[test]
+fn same_image_on_any_number_of_threads() {
+    // Filters wider than a pixel splat across tiles, which are merged in a fixed order.
+    let settings = RenderSettings {
+        tile_size: 8,
+        filter: Filter::new(FilterKind::Gaussian),
+        ..settings()
+    };
+    let reference = render(&settings, 1);
+    assert!(reference.pixels().iter().any(|c| c.max() > 0.0));
+    for threads in [2, 3, 7] {
+        assert_same(&reference, &render(&settings, threads));
+    }
+}
+
+#[test]
+fn same_image_for_any_tile_size_and_order() {
+    let reference = render(&settings(), 4);
+    for (tile_size, tile_order) in [
+        (1, TileOrder::Spiral),
+        (5, TileOrder::Spiral),
+        (16, TileOrder::Hilbert),
+        (64, TileOrder::Spiral),
+    ] {
+        let settings = RenderSettings {
+            tile_size,
+            tile_order,
+            ..settings()
+        };
+        assert_same(&reference, &render(&settings, 4));
+    }
+}
+
+#[test]
+fn same_image_for_the_same_seed_only() {
+    let settings = settings();
+    assert_same(&render(&settings, 2), &render(&settings, 2));
+
+    let other = render(
+        &RenderSettings {
+            seed: 8,
+            ..settings.clone()
+        },
+        2,
+    );
+    assert_ne!(render(&settings, 2).pixels(), other.pixels());
+}